@@ -2,16 +2,13 @@ use anyhow::Result;
 use smithay::{
     backend::{
         renderer::{
-            element::{texture::TextureRenderElement, Element, RenderElement},
-            gles::GlesTexture,
-            glow::GlowRenderer,
-            Frame, Renderer,
+            element::texture::TextureRenderElement, gles::GlesTexture, glow::GlowRenderer, Frame,
+            Renderer,
         },
         winit,
     },
     input::{
         keyboard::{FilterResult, XkbConfig},
-        pointer::{AxisFrame, ButtonEvent, MotionEvent},
         SeatHandler, SeatState,
     },
     reexports::wayland_server::protocol::wl_surface::WlSurface,
@@ -56,23 +53,17 @@ fn main() -> Result<()> {
     let mut state = State(seat_state);
     let keyboard = seat.add_keyboard(XkbConfig::default(), 200, 25)?;
     keyboard.set_focus(&mut state, Some(egui.clone()), SERIAL_COUNTER.next_serial());
-    let pointer = seat.add_pointer();
 
     loop {
+        let window_size = backend.window_size().to_logical(1);
         input.dispatch_new_events(|event| {
             use smithay::backend::{
-                input::{
-                    AbsolutePositionEvent, Axis, AxisSource, Event, InputEvent, KeyboardKeyEvent,
-                    PointerAxisEvent, PointerButtonEvent,
-                },
+                input::{Event, InputEvent, KeyboardKeyEvent},
                 winit::WinitEvent::*,
             };
-            match event {
-                // Handle input events by passing them into smithay-egui
-                Input(event) => match event {
-                    // egui tracks pointers
-                    InputEvent::DeviceAdded { device } => egui.handle_device_added(&device),
-                    InputEvent::DeviceRemoved { device } => egui.handle_device_added(&device),
+            // Handle input events by passing them into smithay-egui
+            if let Input(event) = event {
+                match event {
                     // we rely on the filter-closure of the keyboard.input call to get the values we need for egui.
                     //
                     // NOTE: usually you would need to check `EguiState::wants_keyboard_input` or track focus of egui
@@ -88,84 +79,22 @@ fn main() -> Result<()> {
                             |_data, _modifiers, _handle| FilterResult::Forward,
                         )
                         .unwrap_or(()),
-                    // Winit only produces `PointerMotionAbsolute` events, but a real compositor needs to handle this for `PointerMotion` events as well.
-                    // Meaning: you need to compute the absolute position and pass that to egui.
-                    InputEvent::PointerMotionAbsolute { event } => {
-                        let pos = event.position();
-                        pointer.motion(
-                            &mut state,
-                            Some((egui.clone(), (0., 0.).into())),
-                            &MotionEvent {
-                                location: (pos.x, pos.y).into(),
-                                serial: SERIAL_COUNTER.next_serial(),
-                                time: event.time_msec(),
-                            },
-                        );
-                    }
-                    // NOTE: you should check with `EguiState::wwants_pointer`, if the pointer is above any egui element before forwarding it.
-                    // Otherwise forward it to clients as usual.
-                    InputEvent::PointerButton { event } => pointer.button(
-                        &mut state,
-                        &ButtonEvent {
-                            button: event.button_code(),
-                            state: event.state().into(),
-                            serial: SERIAL_COUNTER.next_serial(),
-                            time: event.time_msec(),
-                        },
-                    ),
-                    // NOTE: you should check with `EguiState::wwants_pointer`, if the pointer is above any egui element before forwarding it.
+                    // egui tracks pointer devices and handles pointer events directly.
+                    //
+                    // NOTE: you should check with `EguiState::wants_pointer`, if the pointer is above any egui element before forwarding it.
                     // Otherwise forward it to clients as usual.
-                    InputEvent::PointerAxis { event } => {
-                        let horizontal_amount =
-                            event.amount(Axis::Horizontal).unwrap_or_else(|| {
-                                event.amount_v120(Axis::Horizontal).unwrap_or(0.0) * 15.0 / 120.
-                            });
-                        let vertical_amount = event.amount(Axis::Vertical).unwrap_or_else(|| {
-                            event.amount_v120(Axis::Vertical).unwrap_or(0.0) * 15.0 / 120.
-                        });
-                        let horizontal_amount_discrete = event.amount_v120(Axis::Horizontal);
-                        let vertical_amount_discrete = event.amount_v120(Axis::Vertical);
-
-                        {
-                            let mut frame =
-                                AxisFrame::new(event.time_msec()).source(event.source());
-                            if horizontal_amount != 0.0 {
-                                frame = frame.value(Axis::Horizontal, horizontal_amount);
-                                if let Some(discrete) = horizontal_amount_discrete {
-                                    frame = frame.v120(Axis::Horizontal, discrete as i32);
-                                }
-                            } else if event.source() == AxisSource::Finger {
-                                frame = frame.stop(Axis::Horizontal);
-                            }
-                            if vertical_amount != 0.0 {
-                                frame = frame.value(Axis::Vertical, vertical_amount);
-                                if let Some(discrete) = vertical_amount_discrete {
-                                    frame = frame.v120(Axis::Vertical, discrete as i32);
-                                }
-                            } else if event.source() == AxisSource::Finger {
-                                frame = frame.stop(Axis::Vertical);
-                            }
-                            pointer.axis(&mut state, frame);
-                        }
-                    }
-                    _ => {}
-                },
-                _ => {}
+                    event => egui.handle_input_event(&event, window_size),
+                }
             }
         });
 
         let size = backend.window_size();
+        // Just render it over the whole window, but you may limit the area
+        // we also completely ignore the scale *everywhere* in this example, but egui is HiDPI-ready
+        let area = egui.resize_to(size, 1.0)?;
         // Here we compute the rendered egui frame
         let egui_frame: TextureRenderElement<GlesTexture> = egui
-            .render(
-                |ctx| demo_ui.ui(ctx),
-                backend.renderer(),
-                // Just render it over the whole window, but you may limit the area
-                Rectangle::from_size(size.to_logical(1)),
-                // we also completely ignore the scale *everywhere* in this example, but egui is HiDPI-ready
-                1.0,
-                1.0,
-            )
+            .render(|ctx| demo_ui.ui(ctx), backend.renderer(), area, 1.0, 1.0)
             .expect("Failed to render egui");
 
         // Lastly put the rendered frame on the screen
@@ -174,14 +103,7 @@ fn main() -> Result<()> {
         {
             let mut frame = renderer.render(size, Transform::Flipped180)?;
             frame.clear([1.0, 1.0, 1.0, 1.0].into(), &[Rectangle::from_size(size)])?;
-            RenderElement::<GlowRenderer>::draw(
-                &egui_frame,
-                &mut frame,
-                egui_frame.src(),
-                egui_frame.geometry(1.0.into()),
-                &[Rectangle::from_size(size)],
-                &[],
-            )?;
+            egui.draw_to_frame(&mut frame, &egui_frame, 1.0, &[Rectangle::from_size(size)])?;
         }
         backend.submit(None)?;
     }
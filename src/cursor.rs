@@ -0,0 +1,93 @@
+use egui::CursorIcon;
+use smithay::input::pointer::CursorIcon as NamedCursor;
+use smithay::reexports::wayland_protocols::wp::cursor_shape::v1::server::wp_cursor_shape_device_v1::Shape;
+
+/// Converts an [`egui::CursorIcon`] into the matching `wp_cursor_shape_v1` shape.
+///
+/// Returns `None` for [`CursorIcon::None`], meaning the cursor should be hidden.
+pub fn egui_cursor_to_shape(icon: CursorIcon) -> Option<Shape> {
+    Some(match icon {
+        CursorIcon::None => return None,
+        CursorIcon::Default => Shape::Default,
+        CursorIcon::ContextMenu => Shape::ContextMenu,
+        CursorIcon::Help => Shape::Help,
+        CursorIcon::PointingHand => Shape::Pointer,
+        CursorIcon::Progress => Shape::Progress,
+        CursorIcon::Wait => Shape::Wait,
+        CursorIcon::Cell => Shape::Cell,
+        CursorIcon::Crosshair => Shape::Crosshair,
+        CursorIcon::Text => Shape::Text,
+        CursorIcon::VerticalText => Shape::VerticalText,
+        CursorIcon::Alias => Shape::Alias,
+        CursorIcon::Copy => Shape::Copy,
+        CursorIcon::Move => Shape::Move,
+        CursorIcon::NoDrop => Shape::NoDrop,
+        CursorIcon::NotAllowed => Shape::NotAllowed,
+        CursorIcon::Grab => Shape::Grab,
+        CursorIcon::Grabbing => Shape::Grabbing,
+        CursorIcon::AllScroll => Shape::AllScroll,
+        CursorIcon::ResizeHorizontal => Shape::EwResize,
+        CursorIcon::ResizeNeSw => Shape::NeswResize,
+        CursorIcon::ResizeNwSe => Shape::NwseResize,
+        CursorIcon::ResizeVertical => Shape::NsResize,
+        CursorIcon::ResizeEast => Shape::EResize,
+        CursorIcon::ResizeSouthEast => Shape::SeResize,
+        CursorIcon::ResizeSouth => Shape::SResize,
+        CursorIcon::ResizeSouthWest => Shape::SwResize,
+        CursorIcon::ResizeWest => Shape::WResize,
+        CursorIcon::ResizeNorthWest => Shape::NwResize,
+        CursorIcon::ResizeNorth => Shape::NResize,
+        CursorIcon::ResizeNorthEast => Shape::NeResize,
+        CursorIcon::ResizeColumn => Shape::ColResize,
+        CursorIcon::ResizeRow => Shape::RowResize,
+        CursorIcon::ZoomIn => Shape::ZoomIn,
+        CursorIcon::ZoomOut => Shape::ZoomOut,
+    })
+}
+
+/// Converts an [`egui::CursorIcon`] into the matching cursor name of the XDG cursor spec (which uses CSS names).
+///
+/// Cursor themes are looked up by these names, e.g. for [`CursorImageStatus::Named`](smithay::input::pointer::CursorImageStatus::Named).
+/// Returns `None` for [`CursorIcon::None`], meaning the cursor should be hidden.
+pub fn convert_cursor_icon(icon: CursorIcon) -> Option<&'static str> {
+    Some(
+        match icon {
+            CursorIcon::None => return None,
+            CursorIcon::Default => NamedCursor::Default,
+            CursorIcon::ContextMenu => NamedCursor::ContextMenu,
+            CursorIcon::Help => NamedCursor::Help,
+            CursorIcon::PointingHand => NamedCursor::Pointer,
+            CursorIcon::Progress => NamedCursor::Progress,
+            CursorIcon::Wait => NamedCursor::Wait,
+            CursorIcon::Cell => NamedCursor::Cell,
+            CursorIcon::Crosshair => NamedCursor::Crosshair,
+            CursorIcon::Text => NamedCursor::Text,
+            CursorIcon::VerticalText => NamedCursor::VerticalText,
+            CursorIcon::Alias => NamedCursor::Alias,
+            CursorIcon::Copy => NamedCursor::Copy,
+            CursorIcon::Move => NamedCursor::Move,
+            CursorIcon::NoDrop => NamedCursor::NoDrop,
+            CursorIcon::NotAllowed => NamedCursor::NotAllowed,
+            CursorIcon::Grab => NamedCursor::Grab,
+            CursorIcon::Grabbing => NamedCursor::Grabbing,
+            CursorIcon::AllScroll => NamedCursor::AllScroll,
+            CursorIcon::ResizeHorizontal => NamedCursor::EwResize,
+            CursorIcon::ResizeNeSw => NamedCursor::NeswResize,
+            CursorIcon::ResizeNwSe => NamedCursor::NwseResize,
+            CursorIcon::ResizeVertical => NamedCursor::NsResize,
+            CursorIcon::ResizeEast => NamedCursor::EResize,
+            CursorIcon::ResizeSouthEast => NamedCursor::SeResize,
+            CursorIcon::ResizeSouth => NamedCursor::SResize,
+            CursorIcon::ResizeSouthWest => NamedCursor::SwResize,
+            CursorIcon::ResizeWest => NamedCursor::WResize,
+            CursorIcon::ResizeNorthWest => NamedCursor::NwResize,
+            CursorIcon::ResizeNorth => NamedCursor::NResize,
+            CursorIcon::ResizeNorthEast => NamedCursor::NeResize,
+            CursorIcon::ResizeColumn => NamedCursor::ColResize,
+            CursorIcon::ResizeRow => NamedCursor::RowResize,
+            CursorIcon::ZoomIn => NamedCursor::ZoomIn,
+            CursorIcon::ZoomOut => NamedCursor::ZoomOut,
+        }
+        .name(),
+    )
+}
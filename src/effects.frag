@@ -0,0 +1,67 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+#if defined(GL_FRAGMENT_PRECISION_HIGH)
+precision highp float;
+#else
+precision mediump float;
+#endif
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+// size of the whole surface in pixels
+uniform vec2 size;
+// radius of the rounded corners in pixels
+uniform float corner_radius;
+// applied to the rgb components of every pixel
+uniform mat3 color_transform;
+// 0.0 turns every pixel gray, 1.0 keeps the colors
+uniform float saturation;
+// multiplied with every pixel (premultiplied)
+uniform vec4 color_tint;
+
+float rounded_corner_mask(vec2 pos) {
+    vec2 half_size = size / 2.0;
+    float radius = min(corner_radius, min(half_size.x, half_size.y));
+    vec2 q = abs(pos - half_size) - (half_size - vec2(radius));
+    float dist = length(max(q, 0.0)) - radius;
+    return clamp(0.5 - dist, 0.0, 1.0);
+}
+
+void main() {
+    vec4 color = texture2D(tex, v_coords);
+
+#if defined(NO_ALPHA)
+    color = vec4(color.rgb, 1.0);
+#endif
+
+    color.rgb = color_transform * color.rgb;
+    float luma = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+    color.rgb = mix(vec3(luma), color.rgb, saturation);
+    // colors are premultiplied, so the transformed ones must not exceed alpha
+    color.rgb = clamp(color.rgb, 0.0, color.a);
+    color = color * color_tint;
+    color = color * rounded_corner_mask(v_coords * size) * alpha;
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        color = vec4(0.0, 0.2, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
@@ -0,0 +1,86 @@
+// Post-processing applied to the whole egui surface, after egui itself was painted.
+
+use egui::Color32;
+use smithay::{
+    backend::renderer::gles::{
+        GlesError, GlesRenderer, GlesTexProgram, Uniform, UniformName, UniformType, UniformValue,
+    },
+    utils::{Physical, Size},
+};
+
+const SHADER: &str = include_str!("effects.frag");
+
+/// A color transformation applied to the whole egui surface, see [`EguiState::set_color_transform`]
+///
+/// The matrix is given in row-major order and multiplied with the rgb components of every pixel,
+/// e.g. to match a night-light filter or color-blindness simulation applied to the rest of the screen.
+///
+/// [`EguiState::set_color_transform`]: crate::EguiState::set_color_transform
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorTransform(pub [[f32; 3]; 3]);
+
+impl ColorTransform {
+    /// The transformation leaving all colors untouched
+    pub const IDENTITY: ColorTransform =
+        ColorTransform([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+    fn to_column_major(self) -> [f32; 9] {
+        let m = self.0;
+        [
+            m[0][0], m[1][0], m[2][0], m[0][1], m[1][1], m[2][1], m[0][2], m[1][2], m[2][2],
+        ]
+    }
+}
+
+impl Default for ColorTransform {
+    fn default() -> Self {
+        ColorTransform::IDENTITY
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Effects {
+    /// Radius of the rounded corners in logical pixels
+    pub corner_radius: f32,
+    pub color_transform: Option<ColorTransform>,
+    /// Color multiplied with every pixel and the saturation applied before
+    pub tint: Option<(Color32, f32)>,
+}
+
+impl Effects {
+    /// Returns true, if any effect needs to be applied
+    pub fn is_active(&self) -> bool {
+        self.corner_radius > 0.0 || self.color_transform.is_some() || self.tint.is_some()
+    }
+
+    pub fn uniforms(&self, size: Size<i32, Physical>, scale: f32) -> Vec<Uniform<'static>> {
+        let (tint, saturation) = self.tint.unwrap_or((Color32::WHITE, 1.0));
+        vec![
+            Uniform::new("size", (size.w as f32, size.h as f32)),
+            Uniform::new("corner_radius", self.corner_radius * scale),
+            Uniform::new(
+                "color_transform",
+                UniformValue::Matrix3x3 {
+                    matrices: vec![self.color_transform.unwrap_or_default().to_column_major()],
+                    // GLES2 doesn't support transposing
+                    transpose: false,
+                },
+            ),
+            Uniform::new("saturation", saturation),
+            Uniform::new("color_tint", tint.to_normalized_gamma_f32()),
+        ]
+    }
+}
+
+pub fn compile_program(renderer: &mut GlesRenderer) -> Result<GlesTexProgram, GlesError> {
+    renderer.compile_custom_texture_shader(
+        SHADER,
+        &[
+            UniformName::new("size", UniformType::_2f),
+            UniformName::new("corner_radius", UniformType::_1f),
+            UniformName::new("color_transform", UniformType::Matrix3x3),
+            UniformName::new("saturation", UniformType::_1f),
+            UniformName::new("color_tint", UniformType::_4f),
+        ],
+    )
+}
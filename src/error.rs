@@ -0,0 +1,42 @@
+use smithay::backend::{egl::MakeCurrentError, renderer::gles::GlesError};
+
+/// Errors that can occur while rendering egui
+#[derive(Debug, thiserror::Error)]
+pub enum EguiError {
+    /// The given scale factor was zero, negative or not a number
+    #[error("Invalid scale factor: {0}")]
+    InvalidScale(f64),
+    /// The GPU state was suspended and not resumed yet, see `EguiState::resume`
+    #[error("Rendering is suspended")]
+    Suspended,
+    /// A GL call of the given step failed, only reported with the `gl_debug` feature
+    #[cfg(feature = "gl_debug")]
+    #[error("GL error {error:#x} during: {step}")]
+    Gl {
+        /// The step of the render path, that failed
+        step: &'static str,
+        /// The error returned by `glGetError`
+        error: u32,
+    },
+    /// egui's painter could not be created, e.g. because its shaders failed to compile
+    #[error("Failed to create the egui painter: {0}")]
+    Painter(#[source] egui_glow::PainterError),
+    /// The buffer egui is rendered into could not be allocated
+    #[error("Failed to allocate a buffer for egui")]
+    BufferAllocation(#[source] GlesError),
+    /// The egl context of the renderer could not be made current, e.g. because it was lost
+    #[error("The egl context is lost")]
+    ContextLost(#[source] MakeCurrentError),
+    /// The underlying renderer failed
+    #[error(transparent)]
+    Gles(GlesError),
+}
+
+impl From<GlesError> for EguiError {
+    fn from(err: GlesError) -> Self {
+        match err {
+            GlesError::ContextActivationError(err) => EguiError::ContextLost(err),
+            err => EguiError::Gles(err),
+        }
+    }
+}
@@ -3,7 +3,7 @@
 use egui::{Key, Modifiers, PointerButton};
 use smithay::{
     backend::input::MouseButton,
-    input::keyboard::{Keysym as KeysymU32, ModifiersState},
+    input::keyboard::{Keysym as KeysymU32, ModifiersState, SerializedMods, Xkb},
 };
 use xkbcommon::xkb;
 pub use xkbcommon::xkb::{Keycode, Keysym};
@@ -11,6 +11,8 @@ pub use xkbcommon::xkb::{Keycode, Keysym};
 use std::convert::TryFrom;
 
 pub struct KbdInternal {
+    // either a default keymap or a reference to the compositor's keymap.
+    // holding the reference keeps its address from being reused, so keymaps can be compared by address.
     keymap: xkb::Keymap,
     state: xkb::State,
 }
@@ -43,6 +45,30 @@ impl KbdInternal {
         Some(KbdInternal { keymap, state })
     }
 
+    // switch to the compositor's keymap, if it changed since the last call
+    pub fn sync_keymap(&mut self, xkb: &Xkb) {
+        // SAFETY: compiled keymaps are immutable, only their reference count isn't thread-safe.
+        // Our reference is taken and released here, while the caller holds the lock of `xkb`.
+        let keymap = unsafe { xkb.keymap() };
+        if keymap.get_raw_ptr() == self.keymap.get_raw_ptr() {
+            return;
+        }
+        self.keymap = keymap.clone();
+        self.state = xkb::State::new(&self.keymap);
+    }
+
+    // apply the compositor's modifiers, so that e.g. AltGr (Level3) and Level5 shifted keys produce the right text
+    pub fn update_modifiers(&mut self, mods: SerializedMods) {
+        self.state.update_mask(
+            mods.depressed,
+            mods.latched,
+            mods.locked,
+            0,
+            0,
+            mods.layout_effective,
+        );
+    }
+
     // return true if modifier state has changed
     pub fn key_input(&mut self, keycode: u32, pressed: bool) {
         let direction = match pressed {
@@ -57,6 +83,11 @@ impl KbdInternal {
     pub fn get_utf8(&self, keycode: u32) -> String {
         self.state.key_get_utf8(Keycode::new(keycode))
     }
+
+    // false for keys that should not repeat, like modifiers
+    pub fn key_repeats(&self, keycode: u32) -> bool {
+        self.keymap.key_repeats(Keycode::new(keycode))
+    }
 }
 
 /// Converts a set of raw keycodes into [`egui::Key`], if possible.
@@ -79,31 +110,49 @@ impl TryFrom<KeysymConv> for Key {
 
         #[allow(non_upper_case_globals)]
         Ok(match sym.0 {
-            Keysym::Down => ArrowDown,
-            Keysym::Left => ArrowLeft,
-            Keysym::Right => ArrowRight,
-            Keysym::Up => ArrowUp,
+            Keysym::Down | Keysym::KP_Down => ArrowDown,
+            Keysym::Left | Keysym::KP_Left => ArrowLeft,
+            Keysym::Right | Keysym::KP_Right => ArrowRight,
+            Keysym::Up | Keysym::KP_Up => ArrowUp,
             Keysym::Escape => Escape,
             Keysym::Tab => Tab,
             Keysym::BackSpace => Backspace,
-            Keysym::Return => Enter,
+            Keysym::Return | Keysym::KP_Enter => Enter,
             Keysym::space => Space,
-            Keysym::Insert => Insert,
-            Keysym::Delete => Delete,
-            Keysym::Home => Home,
-            Keysym::End => End,
-            Keysym::Page_Up => PageUp,
-            Keysym::Page_Down => PageDown,
-            Keysym::_0 => Num0,
-            Keysym::_1 => Num1,
-            Keysym::_2 => Num2,
-            Keysym::_3 => Num3,
-            Keysym::_4 => Num4,
-            Keysym::_5 => Num5,
-            Keysym::_6 => Num6,
-            Keysym::_7 => Num7,
-            Keysym::_8 => Num8,
-            Keysym::_9 => Num9,
+            Keysym::Insert | Keysym::KP_Insert => Insert,
+            Keysym::Delete | Keysym::KP_Delete => Delete,
+            Keysym::Home | Keysym::KP_Home => Home,
+            Keysym::End | Keysym::KP_End => End,
+            Keysym::Page_Up | Keysym::KP_Page_Up => PageUp,
+            Keysym::Page_Down | Keysym::KP_Page_Down => PageDown,
+            Keysym::XF86_Copy => Copy,
+            Keysym::XF86_Cut => Cut,
+            Keysym::XF86_Paste => Paste,
+            Keysym::colon => Colon,
+            Keysym::comma | Keysym::KP_Separator => Comma,
+            Keysym::backslash => Backslash,
+            Keysym::slash | Keysym::KP_Divide => Slash,
+            Keysym::bar => Pipe,
+            Keysym::question => Questionmark,
+            Keysym::bracketleft => OpenBracket,
+            Keysym::bracketright => CloseBracket,
+            Keysym::grave => Backtick,
+            Keysym::minus | Keysym::KP_Subtract => Minus,
+            Keysym::period | Keysym::KP_Decimal => Period,
+            Keysym::plus | Keysym::KP_Add => Plus,
+            Keysym::equal | Keysym::KP_Equal => Equals,
+            Keysym::semicolon => Semicolon,
+            Keysym::apostrophe => Quote,
+            Keysym::_0 | Keysym::KP_0 => Num0,
+            Keysym::_1 | Keysym::KP_1 => Num1,
+            Keysym::_2 | Keysym::KP_2 => Num2,
+            Keysym::_3 | Keysym::KP_3 => Num3,
+            Keysym::_4 | Keysym::KP_4 => Num4,
+            Keysym::_5 | Keysym::KP_5 => Num5,
+            Keysym::_6 | Keysym::KP_6 => Num6,
+            Keysym::_7 | Keysym::KP_7 => Num7,
+            Keysym::_8 | Keysym::KP_8 => Num8,
+            Keysym::_9 | Keysym::KP_9 => Num9,
             Keysym::a => A,
             Keysym::b => B,
             Keysym::c => C,
@@ -130,6 +179,41 @@ impl TryFrom<KeysymConv> for Key {
             Keysym::x => X,
             Keysym::y => Y,
             Keysym::z => Z,
+            Keysym::F1 => F1,
+            Keysym::F2 => F2,
+            Keysym::F3 => F3,
+            Keysym::F4 => F4,
+            Keysym::F5 => F5,
+            Keysym::F6 => F6,
+            Keysym::F7 => F7,
+            Keysym::F8 => F8,
+            Keysym::F9 => F9,
+            Keysym::F10 => F10,
+            Keysym::F11 => F11,
+            Keysym::F12 => F12,
+            Keysym::F13 => F13,
+            Keysym::F14 => F14,
+            Keysym::F15 => F15,
+            Keysym::F16 => F16,
+            Keysym::F17 => F17,
+            Keysym::F18 => F18,
+            Keysym::F19 => F19,
+            Keysym::F20 => F20,
+            Keysym::F21 => F21,
+            Keysym::F22 => F22,
+            Keysym::F23 => F23,
+            Keysym::F24 => F24,
+            Keysym::F25 => F25,
+            Keysym::F26 => F26,
+            Keysym::F27 => F27,
+            Keysym::F28 => F28,
+            Keysym::F29 => F29,
+            Keysym::F30 => F30,
+            Keysym::F31 => F31,
+            Keysym::F32 => F32,
+            Keysym::F33 => F33,
+            Keysym::F34 => F34,
+            Keysym::F35 => F35,
             _ => {
                 return Err(());
             }
@@ -169,6 +253,28 @@ pub fn convert_button(button: MouseButton) -> Option<PointerButton> {
     ButtonWrapper(button).try_into().ok()
 }
 
+/// Convert a button code from `<linux/input-event-codes.h>` to egui's [`PointerButton`], if possible
+///
+/// This uses the same table as [`smithay::backend::input::PointerButtonEvent::button`].
+pub fn convert_button_code(code: u32) -> Option<PointerButton> {
+    const BTN_LEFT: u32 = 0x110;
+    const BTN_RIGHT: u32 = 0x111;
+    const BTN_MIDDLE: u32 = 0x112;
+    const BTN_SIDE: u32 = 0x113;
+    const BTN_EXTRA: u32 = 0x114;
+    const BTN_FORWARD: u32 = 0x115;
+    const BTN_BACK: u32 = 0x116;
+
+    convert_button(match code {
+        BTN_LEFT => MouseButton::Left,
+        BTN_RIGHT => MouseButton::Right,
+        BTN_MIDDLE => MouseButton::Middle,
+        BTN_BACK | BTN_SIDE => MouseButton::Back,
+        BTN_FORWARD | BTN_EXTRA => MouseButton::Forward,
+        _ => return None,
+    })
+}
+
 pub struct ButtonWrapper(pub MouseButton);
 
 impl TryFrom<ButtonWrapper> for PointerButton {
@@ -179,6 +285,8 @@ impl TryFrom<ButtonWrapper> for PointerButton {
             MouseButton::Left => PointerButton::Primary,
             MouseButton::Middle => PointerButton::Middle,
             MouseButton::Right => PointerButton::Secondary,
+            MouseButton::Back => PointerButton::Extra1,
+            MouseButton::Forward => PointerButton::Extra2,
             _ => {
                 return Err(());
             }
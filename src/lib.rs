@@ -1,24 +1,38 @@
+//! Integration of [`egui`] into [`smithay`] compositors
+//!
+//! See [`EguiState`] for passing input to egui and rendering it with a [`GlesRenderer`].
+#![deny(missing_docs)]
+
 use egui::PlatformOutput;
-#[deny(missing_docs)]
-use egui::{Context, Event, FullOutput, Pos2, RawInput, Rect, Vec2};
-use egui_glow::Painter;
+use egui::{
+    epaint::{ClippedShape, ImageDelta, Primitive, TextureAtlas},
+    ClippedPrimitive, Color32, Context, CursorIcon, Event, FullOutput, PointerButton, Pos2,
+    RawInput, Rect, Rgba, TextureId, TexturesDelta, Vec2, ViewportId, ViewportIdMap, ViewportInfo,
+    ViewportOutput,
+};
+use egui_glow::{
+    glow::{self, HasContext},
+    Painter,
+};
 #[cfg(feature = "desktop_integration")]
-use smithay::desktop::space::SpaceElement;
+use smithay::desktop::space::{RenderZindex, SpaceElement};
 use smithay::{
     backend::{
         allocator::Fourcc,
-        input::{ButtonState, Device, DeviceCapability, KeyState, MouseButton},
+        input::{
+            AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability,
+            InputBackend, InputEvent, KeyState, MouseButton, PointerAxisEvent, PointerButtonEvent,
+            PointerMotionEvent, TouchEvent, TouchSlot,
+        },
         renderer::{
             element::{
                 texture::{TextureRenderBuffer, TextureRenderElement},
-                Kind,
+                Element, Kind, RenderElement,
             },
-            gles::{GlesError, GlesTexture},
-            glow::GlowRenderer,
-            Bind, Frame, Offscreen, Renderer, Unbind,
+            gles::{GlesFrame, GlesRenderer, GlesTexProgram, GlesTexture},
+            Bind, Frame, Offscreen, Renderer, Texture, Unbind,
         },
     },
-    desktop::space::RenderZindex,
     input::{
         keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
         pointer::{
@@ -27,23 +41,50 @@ use smithay::{
             GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent, MotionEvent,
             PointerTarget, RelativeMotionEvent,
         },
+        touch::{
+            DownEvent, MotionEvent as TouchMotionEvent, OrientationEvent, ShapeEvent, TouchTarget,
+            UpEvent,
+        },
         Seat, SeatHandler,
     },
-    utils::{IsAlive, Logical, Physical, Point, Rectangle, Serial, Size, Transform},
+    output::{Output, WeakOutput},
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
+    utils::{IsAlive, Logical, Physical, Point, Rectangle, Scale, Serial, Size, Transform},
 };
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
 use xkbcommon::xkb::Keycode;
 
 use std::{
+    borrow::BorrowMut,
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt,
+    num::NonZeroUsize,
     rc::Rc,
-    sync::{Arc, Mutex},
-    time::Instant,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
+mod cursor;
+mod effects;
+mod error;
 mod input;
-pub use self::input::{convert_button, convert_key, convert_modifiers};
+mod msaa;
+#[cfg(test)]
+mod tests;
+pub use self::cursor::{convert_cursor_icon, egui_cursor_to_shape};
+pub use self::effects::ColorTransform;
+pub use self::error::EguiError;
+pub use self::input::{convert_button, convert_button_code, convert_key, convert_modifiers};
+
+/// Number of rects [`EguiState::take_accumulated_damage`] collects, before merging them into one
+const MAX_ACCUMULATED_DAMAGE: usize = 64;
+
+/// How long a hold gesture needs to last to be treated as a secondary click
+const LONG_PRESS_DELAY: Duration = Duration::from_millis(500);
 
 /// smithay-egui state object
 #[derive(Debug, Clone)]
@@ -51,6 +92,52 @@ pub struct EguiState {
     inner: Arc<Mutex<EguiInner>>,
     ctx: Context,
     start_time: Instant,
+    // set by `clone_for_output`, rendering uses the buffers of this output
+    output: Option<WeakOutput>,
+}
+
+/// Identifier of an [`EguiState`], see [`EguiState::id`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EguiId(usize);
+
+/// egui's built-in debug windows, see [`EguiState::show_debug_windows`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DebugWindows {
+    /// Show [`Context::settings_ui`]
+    pub settings: bool,
+    /// Show [`Context::inspection_ui`]
+    pub inspection: bool,
+    /// Show [`Context::memory_ui`]
+    pub memory: bool,
+    /// Show [`Context::texture_ui`]
+    pub textures: bool,
+}
+
+impl DebugWindows {
+    fn show(&self, ctx: &Context) {
+        let window = |show: bool, title: &str, window_ui: fn(&Context, &mut egui::Ui)| {
+            if show {
+                egui::Window::new(title)
+                    .vscroll(true)
+                    .show(ctx, |ui| window_ui(ctx, ui));
+            }
+        };
+        window(self.settings, "Settings", Context::settings_ui);
+        window(self.inspection, "Inspection", Context::inspection_ui);
+        window(self.memory, "Memory", Context::memory_ui);
+        window(self.textures, "Textures", Context::texture_ui);
+    }
+}
+
+/// What egui made of a key event, see [`EguiState::handle_keyboard`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyOutcome {
+    /// The key produced text
+    Text,
+    /// The key is known to egui (e.g. an arrow key or a shortcut), but produced no text
+    Navigation,
+    /// egui has no use for the key, so it may be forwarded e.g. as a global shortcut
+    Ignored,
 }
 
 impl PartialEq for EguiState {
@@ -60,64 +147,360 @@ impl PartialEq for EguiState {
 }
 
 struct EguiInner {
-    pointers: usize,
+    name: Option<String>,
+    // ids of the pointer devices currently present
+    pointers: HashSet<String>,
     last_pointer_position: Point<i32, Logical>,
+    // unclamped, `None` while the pointer is not over this `EguiState`
+    raw_pointer_position: Option<Point<i32, Logical>>,
+    pointer_region: Option<Rectangle<i32, Logical>>,
+    coalesce_motion: bool,
+    // motions closer than this to `last_pointer_position` are dropped
+    motion_dead_zone: f32,
+    pointer_scale: f64,
+    touch_pointer_emulation: bool,
+    // active touch points, with the id egui knows them by and their last position
+    touches: HashMap<TouchSlot, (egui::TouchId, Point<f64, Logical>)>,
+    next_touch_id: u64,
+    // the touch point driving the pointer, while `touch_pointer_emulation` is enabled
+    pointer_touch: Option<TouchSlot>,
     area: Rectangle<i32, Logical>,
+    pixels_per_point: f32,
+    // scales of the outputs passed to `render_for_output`
+    output_scales: HashMap<WeakOutput, f32>,
+    max_texture_side: Option<usize>,
     last_modifiers: ModifiersState,
+    // number of key events at the end of `events`, that were queued before their modifiers were known
+    unsynced_key_events: usize,
     last_output: Option<PlatformOutput>,
+    cursor_icon: CursorIcon,
+    // custom cursor surfaces and their hotspots, see `set_cursor_override`.
+    // a list, as `CursorIcon` only derives `PartialEq` and `Eq` (egui 0.30, src/data/output.rs)
+    cursor_overrides: Vec<(CursorIcon, WlSurface, Point<i32, Logical>)>,
+    mutable_text_under_cursor: bool,
+    text_input_active: bool,
+    requested_repaints: Vec<(ViewportId, Duration)>,
+    // set by `set_max_repaint_rate`, zero if unlimited
+    min_repaint_interval: Duration,
+    last_paint: Option<Instant>,
+    frame_timings: FrameTimings,
+    // set by `request_repaint_after`, until the next frame is painted
+    repaint_deadline: Option<Instant>,
+    wants_keyboard: bool,
+    soft_keyboard_changed: Option<bool>,
+    scroll_consumed: bool,
+    uploaded_textures: bool,
+    // damage of all renders since the last `take_accumulated_damage`
+    accumulated_damage: Vec<Rectangle<i32, Logical>>,
+    // the scroll delta at the start of the pass begun by `begin_pass`, `None` while no pass is open
+    pass_scroll_delta: Option<Vec2>,
+    zoom_modifier: Option<egui::Modifiers>,
+    system_theme: Option<egui::Theme>,
+    debug_windows: DebugWindows,
+    effects: effects::Effects,
+    msaa_samples: u8,
+    hold_start: Option<Instant>,
+    hold_pressed: bool,
+    pressed_buttons: Vec<PointerButton>,
     pressed: Vec<(Option<egui::Key>, Keycode)>,
+    // keys per second and delay in milliseconds, see `set_repeat_info`
+    repeat_info: (i32, i32),
+    key_repeat: Option<KeyRepeat>,
     focused: bool,
+    suspended: bool,
+    invalidate_buffers: bool,
+    // texture updates of passes, that couldn't be painted, e.g. while suspended
+    pending_textures: TexturesDelta,
+    // the generation of the `GlState` the textures of this state were last uploaded to
+    gl_generation: Option<u64>,
     events: Vec<Event>,
     kbd: Option<input::KbdInternal>,
     #[cfg(feature = "desktop_integration")]
     z_index: u8,
+    #[cfg(feature = "desktop_integration")]
+    outputs: Vec<WeakOutput>,
+    // what was painted onto the background layer (e.g. panels) during the last pass
+    #[cfg(feature = "desktop_integration")]
+    background_region: Vec<Rect>,
+    #[cfg(feature = "debug")]
+    last_raw_input: Option<RawInput>,
 }
 
 impl fmt::Debug for EguiInner {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut d = f.debug_struct("EguiInner");
-        d.field("pointers", &self.pointers)
+        d.field("name", &self.name)
+            .field("pointers", &self.pointers)
             .field("last_pointer_position", &self.last_pointer_position)
+            .field("raw_pointer_position", &self.raw_pointer_position)
+            .field("pointer_region", &self.pointer_region)
+            .field("coalesce_motion", &self.coalesce_motion)
+            .field("motion_dead_zone", &self.motion_dead_zone)
+            .field("pointer_scale", &self.pointer_scale)
+            .field("touch_pointer_emulation", &self.touch_pointer_emulation)
+            .field("touches", &self.touches)
+            .field("next_touch_id", &self.next_touch_id)
+            .field("pointer_touch", &self.pointer_touch)
             .field("area", &self.area)
+            .field("pixels_per_point", &self.pixels_per_point)
+            .field("output_scales", &self.output_scales)
+            .field("max_texture_side", &self.max_texture_side)
             .field("last_modifiers", &self.last_modifiers)
+            .field("unsynced_key_events", &self.unsynced_key_events)
             .field("last_output", &self.last_output.as_ref().map(|_| "..."))
+            .field("cursor_icon", &self.cursor_icon)
+            .field("cursor_overrides", &self.cursor_overrides)
+            .field("mutable_text_under_cursor", &self.mutable_text_under_cursor)
+            .field("text_input_active", &self.text_input_active)
+            .field("requested_repaints", &self.requested_repaints)
+            .field("min_repaint_interval", &self.min_repaint_interval)
+            .field("last_paint", &self.last_paint)
+            .field("frame_timings", &self.frame_timings)
+            .field("repaint_deadline", &self.repaint_deadline)
+            .field("wants_keyboard", &self.wants_keyboard)
+            .field("soft_keyboard_changed", &self.soft_keyboard_changed)
+            .field("scroll_consumed", &self.scroll_consumed)
+            .field("uploaded_textures", &self.uploaded_textures)
+            .field("accumulated_damage", &self.accumulated_damage)
+            .field("pass_scroll_delta", &self.pass_scroll_delta)
+            .field("zoom_modifier", &self.zoom_modifier)
+            .field("system_theme", &self.system_theme)
+            .field("debug_windows", &self.debug_windows)
+            .field("effects", &self.effects)
+            .field("msaa_samples", &self.msaa_samples)
+            .field("hold_start", &self.hold_start)
+            .field("hold_pressed", &self.hold_pressed)
+            .field("pressed_buttons", &self.pressed_buttons)
             .field("pressed", &self.pressed)
+            .field("repeat_info", &self.repeat_info)
+            .field("key_repeat", &self.key_repeat)
             .field("focused", &self.focused)
+            .field("suspended", &self.suspended)
+            .field("invalidate_buffers", &self.invalidate_buffers)
+            .field("pending_textures", &self.pending_textures)
+            .field("gl_generation", &self.gl_generation)
             .field("events", &self.events)
             .field("kbd", &self.kbd);
 
         #[cfg(feature = "desktop_integration")]
         {
             d.field("z_index", &self.z_index);
+            d.field("outputs", &self.outputs);
+            d.field("background_region", &self.background_region);
+        }
+        #[cfg(feature = "debug")]
+        {
+            d.field("last_raw_input", &self.last_raw_input);
         }
 
         d.finish()
     }
 }
 
+impl EguiInner {
+    // how this `EguiState` is referred to in logs and GL object labels
+    fn label(&self) -> String {
+        match self.name.as_ref() {
+            Some(name) => format!("egui \"{}\"", name),
+            None => String::from("egui"),
+        }
+    }
+
+    // positions are passed in the space of the compositor, which may be scaled compared to egui's
+    fn scale_position(&self, position: Point<f64, Logical>) -> Point<f64, Logical> {
+        position.upscale(self.pointer_scale)
+    }
+
+    fn pointer_region(&self) -> Rectangle<i32, Logical> {
+        self.pointer_region
+            .unwrap_or_else(|| Rectangle::from_size(self.area.size))
+    }
+
+    fn push_touch(
+        &mut self,
+        id: egui::TouchId,
+        phase: egui::TouchPhase,
+        position: Point<f64, Logical>,
+    ) {
+        self.events.push(Event::Touch {
+            device_id: egui::TouchDeviceId(0),
+            id,
+            phase,
+            pos: Pos2::new(position.x as f32, position.y as f32),
+            force: None,
+        });
+    }
+
+    // a finger doesn't hover, so the pointer is gone once the emulating touch point ends
+    fn end_pointer_touch(&mut self) {
+        if self.pointer_touch.take().is_some() {
+            self.push_pointer_button(PointerButton::Primary, false);
+            self.push_pointer_gone();
+        }
+    }
+
+    fn dispatch_repeat(&mut self, now: Instant) {
+        let (rate, _) = self.repeat_info;
+        let Some(repeat) = self
+            .key_repeat
+            .as_mut()
+            .filter(|repeat| rate > 0 && repeat.next <= now)
+        else {
+            return;
+        };
+        if let Some(key) = repeat.key {
+            self.events.push(Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: true,
+                modifiers: convert_modifiers(self.last_modifiers),
+            });
+        }
+        push_text_event(
+            &mut self.events,
+            &repeat.utf8,
+            repeat.key,
+            self.text_input_active,
+        );
+        // after a stall (e.g. no frames were rendered for a while), repeating every missed
+        // interval at once would flood egui, so repeat only once and continue from now on
+        repeat.next = now + Duration::from_secs(1) / rate as u32;
+    }
+
+    fn push_pointer_gone(&mut self) {
+        self.raw_pointer_position = None;
+        self.events.push(Event::PointerGone);
+    }
+
+    fn push_pointer_button(&mut self, button: PointerButton, pressed: bool) {
+        if pressed {
+            if !self.pressed_buttons.contains(&button) {
+                self.pressed_buttons.push(button);
+            }
+        } else {
+            self.pressed_buttons.retain(|pressed| *pressed != button);
+        }
+
+        let last_pos = self.last_pointer_position;
+        let modifiers = convert_modifiers(self.last_modifiers);
+        self.events.push(Event::PointerButton {
+            pos: Pos2::new(last_pos.x as f32, last_pos.y as f32),
+            button,
+            pressed,
+            modifiers,
+        })
+    }
+}
+
+// the key held down, that is repeated by `EguiState::dispatch_repeat`
+#[derive(Debug, Clone)]
+struct KeyRepeat {
+    key: Option<egui::Key>,
+    code: Keycode,
+    // the text produced by the initial press
+    utf8: String,
+    next: Instant,
+}
+
+// time spent in the steps of the last render, see `EguiState::render_with_budget`
+#[derive(Debug, Clone, Copy, Default)]
+struct FrameTimings {
+    run: Duration,
+    tessellate: Duration,
+    paint: Duration,
+}
+
+/// Render buffers are kept per [`EguiState`] and optionally per [`Output`], see [`EguiState::render_for_output`]
+type BufferKey = (EguiId, Option<WeakOutput>);
+
+struct RenderBuffer {
+    buffer: TextureRenderBuffer<GlesTexture>,
+    size: Size<i32, Logical>,
+    scale: Scale<i32>,
+    // everything egui painted into the buffer lies within `used`, the rest is transparent
+    used: Option<Rectangle<i32, Logical>>,
+}
+
 struct GlState {
+    // unique for every `GlState`, so each `EguiState` notices, when the state it uploaded its textures to was replaced
+    generation: u64,
     painter: Painter,
-    render_buffers: HashMap<usize, TextureRenderBuffer<GlesTexture>>,
+    render_buffers: HashMap<BufferKey, RenderBuffer>,
+    effects_program: Option<GlesTexProgram>,
+    effect_buffers: HashMap<BufferKey, (GlesTexture, effects::Effects)>,
+    msaa_buffers: HashMap<BufferKey, msaa::MsaaBuffer>,
     #[cfg(feature = "image")]
     images: HashMap<String, egui_extras::image::RetainedImage>,
 }
 type UserDataType = Rc<RefCell<GlState>>;
 
+static NEXT_GL_GENERATION: AtomicU64 = AtomicU64::new(0);
+
 impl EguiState {
     /// Creates a new `EguiState`
+    ///
+    /// `area` is the screen egui lays out in. egui keeps everything, including popups and tooltips,
+    /// within that screen by moving them away from its edges, so nothing is ever clipped by the render buffer.
+    /// If tooltips should be able to extend further, e.g. over the rest of an output, make the area
+    /// cover that space and limit the interactive part with [`EguiState::set_pointer_region`] instead.
     pub fn new(area: Rectangle<i32, Logical>) -> EguiState {
         EguiState {
             ctx: Context::default(),
             start_time: Instant::now(),
+            output: None,
             inner: Arc::new(Mutex::new(EguiInner {
-                pointers: 0,
+                name: None,
+                pointers: HashSet::new(),
                 last_pointer_position: (0, 0).into(),
+                raw_pointer_position: None,
+                pointer_region: None,
+                coalesce_motion: true,
+                motion_dead_zone: 0.0,
+                pointer_scale: 1.0,
+                touch_pointer_emulation: true,
+                touches: HashMap::new(),
+                next_touch_id: 0,
+                pointer_touch: None,
                 area,
+                pixels_per_point: 1.0,
+                output_scales: HashMap::new(),
+                max_texture_side: None,
                 last_modifiers: ModifiersState::default(),
+                unsynced_key_events: 0,
                 last_output: None,
+                cursor_icon: CursorIcon::Default,
+                cursor_overrides: Vec::new(),
+                mutable_text_under_cursor: false,
+                text_input_active: false,
+                requested_repaints: Vec::new(),
+                min_repaint_interval: Duration::ZERO,
+                last_paint: None,
+                frame_timings: FrameTimings::default(),
+                repaint_deadline: None,
+                wants_keyboard: false,
+                soft_keyboard_changed: None,
+                scroll_consumed: false,
+                uploaded_textures: false,
+                accumulated_damage: Vec::new(),
+                pass_scroll_delta: None,
+                zoom_modifier: Some(egui::Modifiers::CTRL),
+                system_theme: None,
+                debug_windows: DebugWindows::default(),
+                effects: effects::Effects::default(),
+                msaa_samples: 0,
+                hold_start: None,
+                hold_pressed: false,
+                pressed_buttons: Vec::new(),
                 events: Vec::new(),
                 focused: false,
+                suspended: false,
+                invalidate_buffers: false,
+                pending_textures: TexturesDelta::default(),
+                gl_generation: None,
                 pressed: Vec::new(),
+                repeat_info: (0, 0),
+                key_repeat: None,
                 kbd: match input::KbdInternal::new() {
                     Some(kbd) => Some(kbd),
                     None => {
@@ -127,12 +510,46 @@ impl EguiState {
                 },
                 #[cfg(feature = "desktop_integration")]
                 z_index: RenderZindex::Overlay as u8,
+                #[cfg(feature = "desktop_integration")]
+                outputs: Vec::new(),
+                #[cfg(feature = "desktop_integration")]
+                background_region: Vec::new(),
+                #[cfg(feature = "debug")]
+                last_raw_input: None,
             })),
         }
     }
 
-    fn id(&self) -> usize {
-        Arc::as_ptr(&self.inner) as usize
+    /// Returns a stable identifier of this [`EguiState`], shared by all of its clones
+    pub fn id(&self) -> EguiId {
+        EguiId(Arc::as_ptr(&self.inner) as usize)
+    }
+
+    /// Returns a clone of this [`EguiState`], that renders into separate buffers for `output`
+    ///
+    /// The clone shares the [`Context`] and input with this `EguiState`, so the same ui
+    /// can be shown on multiple outputs without tracking which output a state belongs to.
+    /// Every render function of the clone behaves like [`EguiState::render_for_output`],
+    /// keeping the gpu resources of each output isolated, while still updating the shared ui state.
+    /// The clone is always painted at the current scale of `output`, the `scale` passed to
+    /// its render functions is ignored.
+    ///
+    /// The area is not tracked per output, but is still shared with all other clones:
+    /// [`EguiState::begin_pass`], pointer clamping and hit testing use the area of the most recent
+    /// render of any clone. Pass the same size to every clone's render functions to keep them consistent.
+    pub fn clone_for_output(&self, output: &Output) -> EguiState {
+        EguiState {
+            output: Some(output.downgrade()),
+            ..self.clone()
+        }
+    }
+
+    /// Sets a name for this [`EguiState`] and all of its clones
+    ///
+    /// The name shows up in log messages, in the labels of GL objects (e.g. in gpu captures)
+    /// and in the `Debug` output, to tell multiple `EguiState`s apart.
+    pub fn set_name(&self, name: impl Into<String>) {
+        self.inner.lock().unwrap().name = Some(name.into());
     }
 
     /// Retrieve the underlying [`egui::Context`]
@@ -140,11 +557,212 @@ impl EguiState {
         &self.ctx
     }
 
+    /// Run `f` with the underlying [`egui::Context`], synchronized with [`EguiState::render`].
+    ///
+    /// Use this for heavier setup like installing loaders or plugins,
+    /// as the closure is guaranteed to not race a concurrent `render` call.
+    ///
+    /// Note: Calling other methods of this `EguiState` from within `f` will deadlock.
+    pub fn configure(&self, f: impl FnOnce(&Context)) {
+        let _guard = self.inner.lock().unwrap();
+        f(&self.ctx)
+    }
+
+    /// Run `f` with the [`egui::Memory`] of the underlying context, synchronized with [`EguiState::render`].
+    ///
+    /// Use this to e.g. programmatically open a window or collapse a header from compositor code.
+    ///
+    /// Note: Calling other methods of this `EguiState` from within `f` will deadlock.
+    pub fn with_memory<R>(&self, f: impl FnOnce(&mut egui::Memory) -> R) -> R {
+        let _guard = self.inner.lock().unwrap();
+        self.ctx.memory_mut(f)
+    }
+
+    /// Adds the font `data` (a `.ttf` or `.otf` file) as `name` to the given `family`
+    ///
+    /// The font is used as a fallback for glyphs the existing fonts of `family` are lacking,
+    /// e.g. to cover CJK characters. Like all setters of `EguiState` this can be called from any thread
+    /// and is synchronized with [`EguiState::render`], the font is used from the next frame on.
+    pub fn add_font(&self, name: &str, data: Vec<u8>, family: egui::FontFamily) {
+        let _guard = self.inner.lock().unwrap();
+        self.ctx.add_font(egui::epaint::text::FontInsert::new(
+            name,
+            egui::FontData::from_owned(data),
+            vec![egui::epaint::text::InsertFontFamily {
+                family,
+                priority: egui::epaint::text::FontPriority::Lowest,
+            }],
+        ));
+    }
+
+    /// Replaces all fonts of the underlying context with `fonts`, starting with the next frame
+    ///
+    /// Like [`EguiState::add_font`] this is synchronized with [`EguiState::render`].
+    pub fn set_fonts(&self, fonts: egui::FontDefinitions) {
+        let _guard = self.inner.lock().unwrap();
+        self.ctx.set_fonts(fonts);
+    }
+
+    /// Serializes the memory of the underlying context, like window positions, scroll offsets and collapsed headers
+    ///
+    /// Pass the result to [`EguiState::load_memory`] to restore it e.g. after a compositor restart.
+    #[cfg(feature = "persistence")]
+    pub fn save_memory(&self) -> Vec<u8> {
+        let _guard = self.inner.lock().unwrap();
+        match self.ctx.memory(ron::to_string) {
+            Ok(data) => data.into_bytes(),
+            Err(err) => {
+                log::warn!("Failed to serialize egui memory: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Restores memory previously returned by [`EguiState::save_memory`]
+    ///
+    /// Data that can't be read, e.g. because it was written by a different egui version, is discarded
+    /// and the current memory is kept.
+    #[cfg(feature = "persistence")]
+    pub fn load_memory(&self, data: &[u8]) {
+        let _guard = self.inner.lock().unwrap();
+        match ron::de::from_bytes::<egui::Memory>(data) {
+            Ok(memory) => self.ctx.memory_mut(|mem| *mem = memory),
+            Err(err) => log::warn!("Discarding incompatible egui memory: {}", err),
+        }
+    }
+
+    /// Registers a custom image loader with the underlying context
+    ///
+    /// Use this to resolve uris of your own scheme passed to e.g. [`egui::Ui::image`],
+    /// like `compositor://thumbnail/<id>` to a texture of a window. Loaders added last are tried first.
+    pub fn add_image_loader(&self, loader: Arc<dyn egui::load::ImageLoader + Send + Sync>) {
+        self.ctx.add_image_loader(loader);
+    }
+
+    /// Sets the default text wrapping of labels and other text, e.g. [`egui::TextWrapMode::Truncate`]
+    ///
+    /// Widgets can still override this individually.
+    /// Like [`EguiState::configure`] this is synchronized with [`EguiState::render`].
+    pub fn set_default_wrap_mode(&self, mode: egui::TextWrapMode) {
+        let _guard = self.inner.lock().unwrap();
+        self.ctx
+            .all_styles_mut(|style| style.wrap_mode = Some(mode));
+    }
+
+    /// Sets how many passes egui may run per [`EguiState::render`] call at most
+    ///
+    /// egui runs another pass, if a widget requests the current one to be discarded,
+    /// e.g. because a table or auto-sized window needs the sizes measured in the first pass.
+    /// The default is 2, `1` disables discarding passes.
+    /// Like [`EguiState::configure`] this is synchronized with [`EguiState::render`].
+    pub fn set_max_passes(&self, max_passes: NonZeroUsize) {
+        let _guard = self.inner.lock().unwrap();
+        self.ctx
+            .options_mut(|options| options.max_passes = max_passes);
+    }
+
+    /// Sets if the text cursor of text fields should blink
+    ///
+    /// Disable this for screenshots or to respect a "reduce motion" preference.
+    /// Like [`EguiState::configure`] this is synchronized with [`EguiState::render`].
+    pub fn set_caret_blink(&self, enabled: bool) {
+        let _guard = self.inner.lock().unwrap();
+        self.ctx
+            .all_styles_mut(|style| style.visuals.text_cursor.blink = enabled);
+    }
+
+    /// Sets the theme preferred by the system, e.g. from the `color-scheme` setting of `org.freedesktop.appearance`
+    ///
+    /// egui follows it, unless a theme was set explicitly (see [`Context::set_theme`]).
+    /// `None` (the default) means the preference is unknown.
+    pub fn set_system_theme(&self, theme: Option<egui::Theme>) {
+        self.inner.lock().unwrap().system_theme = theme;
+        self.ctx.request_repaint();
+    }
+
+    /// Derives the colors of egui from a system `accent` and optionally `background` color
+    ///
+    /// Selections, hyperlinks, the text cursor and hovered or active widgets are tinted with `accent`.
+    /// If `background` is given, panels and windows are filled with it
+    /// and egui switches to its dark or light theme to match its brightness.
+    /// Like [`EguiState::configure`] this is synchronized with [`EguiState::render`].
+    pub fn apply_accent(&self, accent: Color32, background: Option<Color32>) {
+        let _guard = self.inner.lock().unwrap();
+        let theme = match background {
+            Some(background) => {
+                let theme = if Rgba::from(background).intensity() < 0.5 {
+                    egui::Theme::Dark
+                } else {
+                    egui::Theme::Light
+                };
+                self.ctx.set_theme(theme);
+                theme
+            }
+            None => self.ctx.theme(),
+        };
+        // text drawn on top of the accent needs to stay readable
+        let on_accent = if Rgba::from(accent).intensity() < 0.5 {
+            Color32::WHITE
+        } else {
+            Color32::BLACK
+        };
+
+        self.ctx.style_mut_of(theme, |style| {
+            let visuals = &mut style.visuals;
+            if let Some(background) = background {
+                let contrast = match theme {
+                    egui::Theme::Dark => Color32::BLACK,
+                    egui::Theme::Light => Color32::WHITE,
+                };
+                visuals.panel_fill = background;
+                visuals.window_fill = background;
+                visuals.extreme_bg_color = background.lerp_to_gamma(contrast, 0.5);
+                visuals.faint_bg_color = background.lerp_to_gamma(contrast, 0.1);
+            }
+            visuals.selection.bg_fill = accent;
+            visuals.selection.stroke.color = on_accent;
+            visuals.hyperlink_color = accent;
+            visuals.text_cursor.stroke.color = accent;
+            visuals.widgets.hovered.bg_stroke.color = accent;
+            visuals.widgets.active.bg_fill = accent;
+            visuals.widgets.active.weak_bg_fill = accent;
+            visuals.widgets.active.bg_stroke.color = accent;
+            visuals.widgets.active.fg_stroke.color = on_accent;
+        });
+    }
+
     /// If true, egui is currently listening on text input (e.g. typing text in a TextEdit).
     pub fn wants_keyboard(&self) -> bool {
         self.ctx.wants_keyboard_input()
     }
 
+    /// Reports if egui started or stopped listening on text input during the last [`EguiState::render`] call.
+    ///
+    /// Returns `Some(true)` on the frame a text field gained focus, `Some(false)` on the frame it lost focus
+    /// and `None` otherwise. Useful to show or hide an on-screen keyboard at the right time.
+    pub fn soft_keyboard_changed(&self) -> Option<bool> {
+        self.inner.lock().unwrap().soft_keyboard_changed
+    }
+
+    /// Resizes the area of this [`EguiState`] to cover `size` physical pixels at the given `scale`, keeping its location.
+    ///
+    /// Returns the new area, to be passed to [`EguiState::render`] together with the same `scale`,
+    /// e.g. to cover the whole window of a winit backend. The underlying buffer is resized on the next render.
+    ///
+    /// Returns [`EguiError::InvalidScale`] if `scale` is not a positive number.
+    pub fn resize_to(
+        &self,
+        size: Size<i32, Physical>,
+        scale: f64,
+    ) -> Result<Rectangle<i32, Logical>, EguiError> {
+        validate_scale(Scale::from(scale))?;
+        let mut inner = self.inner.lock().unwrap();
+        inner.area.size = size.to_f64().to_logical(scale).to_i32_round();
+        inner.pixels_per_point = pixels_per_point(Scale::from(scale));
+        self.ctx.request_repaint();
+        Ok(inner.area)
+    }
+
     /// True if egui is currently interested in the pointer (mouse or touch).
     /// Could be the pointer is hovering over a Window or the user is dragging a widget.
     /// If false, the pointer is outside of any egui area and so you may want to forward it to other clients as usual.
@@ -153,21 +771,152 @@ impl EguiState {
         self.ctx.wants_pointer_input()
     }
 
+    /// True if egui has a popup (e.g. of a combo box) or a context menu open.
+    ///
+    /// Use this to keep routing input to egui (e.g. by holding a grab) for as long as the menu is open.
+    /// Menus of a menu bar are not included, as egui doesn't track them globally.
+    pub fn has_open_popup(&self) -> bool {
+        self.ctx.memory(|memory| memory.any_popup_open()) || self.ctx.is_context_menu_open()
+    }
+
+    /// True if the last pointer position lies within the area of this [`EguiState`].
+    ///
+    /// This is a purely geometric test, independent of [`EguiState::wants_pointer`],
+    /// e.g. to auto-hide a panel once the pointer left it.
+    /// Returns false after the pointer left this [`EguiState`] as a [`PointerTarget`]
+    /// or the last pointer device was removed.
+    pub fn pointer_in_area(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .raw_pointer_position
+            .is_some_and(|position| Rectangle::from_size(inner.area.size).contains(position))
+    }
+
+    /// True if an enabled, interactive (clickable or draggable) widget is at `point`.
+    ///
+    /// Unlike [`EguiState::wants_pointer`] this ignores decorative content like labels or window backgrounds,
+    /// so clicks on those can be passed through.
+    /// `point` uses the same coordinate space as [`EguiState::handle_pointer_motion`].
+    ///
+    /// Note: egui only keeps track of the widgets under the pointer, so this is only accurate
+    /// for points close to the last pointer position of the previous frame.
+    pub fn interactive_at(&self, point: Point<f64, Logical>) -> bool {
+        let point = self.inner.lock().unwrap().scale_position(point);
+        let pos = Pos2::new(point.x as f32, point.y as f32);
+        self.ctx
+            .interaction_snapshot(|snapshot| snapshot.contains_pointer.clone())
+            .into_iter()
+            .filter_map(|id| self.ctx.read_response(id))
+            .any(|response| {
+                response.enabled()
+                    && response.sense.interactive()
+                    && response.interact_rect.contains(pos)
+            })
+    }
+
+    /// Returns the pointer buttons egui considered pressed during the last [`EguiState::render`] call.
+    ///
+    /// Useful to coordinate compositor-side button gestures (like middle-click paste) with egui.
+    pub fn held_buttons(&self) -> Vec<PointerButton> {
+        use PointerButton::*;
+        self.ctx.input(|i| {
+            [Primary, Secondary, Middle, Extra1, Extra2]
+                .into_iter()
+                .filter(|button| i.pointer.button_down(*button))
+                .collect()
+        })
+    }
+
     /// Pass new input devices to `EguiState` for internal tracking
+    ///
+    /// Devices are tracked by their [`Device::id`], so adding the same device twice has no effect.
     pub fn handle_device_added(&self, device: &impl Device) {
         if device.has_capability(DeviceCapability::Pointer) {
-            self.inner.lock().unwrap().pointers += 1;
+            self.inner.lock().unwrap().pointers.insert(device.id());
         }
     }
 
     /// Remove input devices to `EguiState` for internal tracking
+    ///
+    /// Once the last pointer device is gone, egui is notified about it.
+    /// Removing a device, that wasn't added before, has no effect.
     pub fn handle_device_removed(&self, device: &impl Device) {
         let mut inner = self.inner.lock().unwrap();
-        if device.has_capability(DeviceCapability::Pointer) {
-            inner.pointers -= 1;
+        if device.has_capability(DeviceCapability::Pointer)
+            && inner.pointers.remove(&device.id())
+            && inner.pointers.is_empty()
+        {
+            inner.push_pointer_gone();
         }
-        if inner.pointers == 0 {
-            inner.events.push(Event::PointerGone);
+    }
+
+    /// Pass an [`InputEvent`] of an [`InputBackend`] directly to `EguiState`.
+    ///
+    /// Dispatches device, pointer and touch events to the matching `handle_*` method,
+    /// e.g. when egui is the only thing receiving input, like in the `integrate` example.
+    /// `size` is the size of the output the events belong to and positions are relative to `area`.
+    ///
+    /// Keyboard events need the keymap state of a [`Seat`], pass them via [`EguiState::handle_keyboard`]
+    /// (e.g. from the filter-closure of [`smithay::input::keyboard::KeyboardHandle::input`]) instead.
+    pub fn handle_input_event<B: InputBackend>(
+        &self,
+        event: &InputEvent<B>,
+        size: Size<i32, Logical>,
+    ) {
+        match event {
+            InputEvent::DeviceAdded { device } => self.handle_device_added(device),
+            InputEvent::DeviceRemoved { device } => self.handle_device_removed(device),
+            InputEvent::PointerMotion { event } => {
+                let position = {
+                    let inner = self.inner.lock().unwrap();
+                    // undo the pointer scale, `handle_pointer_motion` applies it again
+                    inner
+                        .raw_pointer_position
+                        .unwrap_or(inner.last_pointer_position)
+                        .to_f64()
+                        .downscale(inner.pointer_scale)
+                        + event.delta()
+                };
+                self.handle_pointer_motion(position.to_i32_round());
+            }
+            InputEvent::PointerMotionAbsolute { event } => {
+                let area = self.inner.lock().unwrap().area;
+                let position = event.position_transformed(size) - area.loc.to_f64();
+                self.handle_pointer_motion(position.to_i32_round());
+            }
+            InputEvent::TouchDown { event } => {
+                let area = self.inner.lock().unwrap().area;
+                let position = event.position_transformed(size) - area.loc.to_f64();
+                self.handle_touch_down(event.slot(), position);
+            }
+            InputEvent::TouchMotion { event } => {
+                let area = self.inner.lock().unwrap().area;
+                let position = event.position_transformed(size) - area.loc.to_f64();
+                self.handle_touch_motion(event.slot(), position);
+            }
+            InputEvent::TouchUp { event } => self.handle_touch_up(event.slot()),
+            InputEvent::TouchCancel { .. } => self.handle_touch_cancel(),
+            InputEvent::PointerButton { event } => {
+                if let Some(button) = convert_button_code(event.button_code()) {
+                    self.inner
+                        .lock()
+                        .unwrap()
+                        .push_pointer_button(button, event.state() == ButtonState::Pressed);
+                }
+            }
+            // scrolling down is positive for libinput, but negative for egui
+            InputEvent::PointerAxis { event } => match (
+                event.amount_v120(Axis::Horizontal),
+                event.amount_v120(Axis::Vertical),
+            ) {
+                (None, None) => self.handle_pointer_axis(
+                    -event.amount(Axis::Horizontal).unwrap_or(0.0),
+                    -event.amount(Axis::Vertical).unwrap_or(0.0),
+                ),
+                (x_v120, y_v120) => self
+                    .handle_pointer_axis_discrete(-x_v120.unwrap_or(0.0), -y_v120.unwrap_or(0.0)),
+            },
+            _ => {}
         }
     }
 
@@ -178,9 +927,16 @@ impl EguiState {
     ///
     /// You likely want to use the filter-closure of [`smithay::wayland::seat::KeyboardHandle::input`] to optain these values.
     /// Use [`smithay::wayland::seat::KeysymHandle`] and the provided [`smithay::wayland::seat::ModifiersState`].
-    pub fn handle_keyboard(&self, handle: &KeysymHandle, pressed: bool, modifiers: ModifiersState) {
+    ///
+    /// Returns whether the key produced text or is otherwise known to egui. Releases never produce text.
+    pub fn handle_keyboard(
+        &self,
+        handle: &KeysymHandle,
+        pressed: bool,
+        modifiers: ModifiersState,
+    ) -> KeyOutcome {
         let mut inner = self.inner.lock().unwrap();
-        inner.last_modifiers = modifiers;
+        self.update_modifiers(&mut inner, modifiers);
         let key = if let Some(key) = convert_key(handle.raw_syms().iter().copied()) {
             inner.events.push(Event::Key {
                 key,
@@ -198,29 +954,225 @@ impl EguiState {
             inner.pressed.push((key, handle.raw_code()));
         } else {
             inner.pressed.retain(|(_, code)| code != &handle.raw_code());
+            if inner
+                .key_repeat
+                .as_ref()
+                .is_some_and(|repeat| repeat.code == handle.raw_code())
+            {
+                inner.key_repeat = None;
+            }
         }
 
+        let text_input_active = inner.text_input_active;
+        let (rate, delay) = inner.repeat_info;
         if let Some(kbd) = inner.kbd.as_mut() {
+            kbd.sync_keymap(&handle.xkb().lock().unwrap());
             kbd.key_input(handle.raw_code().raw(), pressed);
+            kbd.update_modifiers(modifiers.serialized);
 
             if pressed {
                 let utf8 = kbd.get_utf8(handle.raw_code().raw());
-                /* utf8 contains the utf8 string generated by that keystroke
-                 * it can contain 1, multiple characters, or even be empty
-                 */
-                inner.events.push(Event::Text(utf8));
+                if rate > 0 && kbd.key_repeats(handle.raw_code().raw()) {
+                    inner.key_repeat = Some(KeyRepeat {
+                        key,
+                        code: handle.raw_code(),
+                        utf8: utf8.clone(),
+                        next: Instant::now() + Duration::from_millis(delay.max(0) as u64),
+                    });
+                }
+                if push_text_event(&mut inner.events, &utf8, key, text_input_active) {
+                    return KeyOutcome::Text;
+                }
+            }
+        }
+
+        match key {
+            Some(_) => KeyOutcome::Navigation,
+            None => KeyOutcome::Ignored,
+        }
+    }
+
+    /// Pass a repeated key press into `EguiState`.
+    ///
+    /// Use this, if your compositor already drives key repeat itself (e.g. from the repeat info of the keyboard),
+    /// to pass the repeats to egui as [`Event::Key`] with `repeat` set, together with the repeated text.
+    /// The key needs to be pressed via [`EguiState::handle_keyboard`] first, no release is expected for a repeat.
+    pub fn handle_keyboard_repeat(&self, handle: &KeysymHandle, modifiers: ModifiersState) {
+        let mut inner = self.inner.lock().unwrap();
+        self.update_modifiers(&mut inner, modifiers);
+        let key = convert_key(handle.raw_syms().iter().copied());
+        if let Some(key) = key {
+            inner.events.push(Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: true,
+                modifiers: convert_modifiers(modifiers),
+            });
+        }
+
+        let text_input_active = inner.text_input_active;
+        if let Some(kbd) = inner.kbd.as_mut() {
+            kbd.sync_keymap(&handle.xkb().lock().unwrap());
+            kbd.update_modifiers(modifiers.serialized);
+            let utf8 = kbd.get_utf8(handle.raw_code().raw());
+            push_text_event(&mut inner.events, &utf8, key, text_input_active);
+        }
+    }
+
+    /// Sets the key repeat `rate` in keys per second and the `delay` in milliseconds before repeating starts
+    ///
+    /// This mirrors [`smithay::input::keyboard::KeyboardHandle::change_repeat_info`].
+    /// Keys pressed via [`EguiState::handle_keyboard`] are then repeated by [`EguiState::dispatch_repeat`]
+    /// and on every render. Don't use this together with [`EguiState::handle_keyboard_repeat`].
+    ///
+    /// The default `rate` of 0 disables key repeat.
+    pub fn set_repeat_info(&self, rate: i32, delay: i32) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.repeat_info = (rate, delay);
+        if rate <= 0 {
+            inner.key_repeat = None;
+        }
+    }
+
+    /// Queues a repeat of the held key, if one is due at `now`
+    ///
+    /// Renders do this automatically and [`EguiState::next_frame_deadline`] includes the next repeat,
+    /// so calling this is only necessary to pass repeats to egui independently of rendering.
+    /// Repeats use the modifiers at the time of the repeat and the text of the initial press.
+    /// At most one repeat is queued per call, even if multiple intervals passed since the last one,
+    /// the next repeat is then due one interval after `now`.
+    pub fn dispatch_repeat(&self, now: Instant) {
+        self.inner.lock().unwrap().dispatch_repeat(now);
+    }
+
+    fn update_modifiers(&self, inner: &mut EguiInner, modifiers: ModifiersState) {
+        if convert_modifiers(inner.last_modifiers) != convert_modifiers(modifiers) {
+            // egui only picks up modifiers on the next frame, even if no key event is generated
+            // (e.g. a modifier is held alone), so make sure there is one.
+            self.ctx.request_repaint();
+        }
+        inner.last_modifiers = modifiers;
+
+        // the key events were waiting for this update, so they carry the state at keypress time
+        let unsynced = std::mem::take(&mut inner.unsynced_key_events).min(inner.events.len());
+        let start = inner.events.len() - unsynced;
+        for event in &mut inner.events[start..] {
+            if let Event::Key {
+                modifiers: key_modifiers,
+                ..
+            } = event
+            {
+                *key_modifiers = convert_modifiers(modifiers);
             }
         }
     }
 
     /// Pass new pointer coordinates to `EguiState`
+    ///
+    /// The position is scaled by [`EguiState::set_pointer_scale`] and then clamped to the region
+    /// set by [`EguiState::set_pointer_region`].
+    /// Consecutive motions are merged into one, unless disabled via [`EguiState::set_coalesce_motion`].
+    /// Motions within the dead zone set by [`EguiState::set_motion_dead_zone`] are dropped.
     pub fn handle_pointer_motion(&self, position: Point<i32, Logical>) {
         let mut inner = self.inner.lock().unwrap();
+        let region = inner.pointer_region();
+        let position = inner.scale_position(position.to_f64()).to_i32_round();
+        let entered = inner.raw_pointer_position.is_none();
+        inner.raw_pointer_position = Some(position);
+        let position = position.constrain(region);
+        let delta = (position - inner.last_pointer_position).to_f64();
+        if !entered && delta.x.hypot(delta.y) < inner.motion_dead_zone as f64 {
+            return;
+        }
         inner.last_pointer_position = position;
-        inner.events.push(Event::PointerMoved(Pos2::new(
-            position.x as f32,
-            position.y as f32,
-        )))
+        let event = Event::PointerMoved(Pos2::new(position.x as f32, position.y as f32));
+        let coalesce_motion = inner.coalesce_motion;
+        match inner.events.last_mut() {
+            Some(last @ Event::PointerMoved(_)) if coalesce_motion => *last = event,
+            _ => inner.events.push(event),
+        }
+    }
+
+    /// Inserts `text` into whatever egui widget currently has keyboard focus on the next frame
+    ///
+    /// Useful for voice input, pasting from a menu or automation, without synthesizing keystrokes.
+    /// Line breaks are sent as presses of Enter, so multiline text fields insert newlines
+    /// and single line fields behave like the user pressed Enter.
+    pub fn inject_text(&self, text: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                for pressed in [true, false] {
+                    inner.events.push(Event::Key {
+                        key: egui::Key::Enter,
+                        physical_key: None,
+                        pressed,
+                        repeat: false,
+                        modifiers: egui::Modifiers::NONE,
+                    });
+                }
+            }
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if !line.is_empty() {
+                inner.events.push(Event::Text(line.to_string()));
+            }
+        }
+    }
+
+    /// Pastes `text` from the clipboard into the focused egui widget on the next frame
+    ///
+    /// Call this on the paste shortcut (e.g. Ctrl+V) with the contents of the compositor's selection.
+    /// Unlike [`EguiState::inject_text`], text fields handle the whole `text` as one paste.
+    pub fn handle_paste(&self, text: String) {
+        self.inner.lock().unwrap().events.push(Event::Paste(text));
+    }
+
+    /// Copies the selection of the focused egui widget on the next frame
+    ///
+    /// Call this on the copy shortcut (e.g. Ctrl+C). The copied text can be read with
+    /// [`EguiState::copied_text`] after the next render and put into the compositor's selection.
+    pub fn handle_copy(&self) {
+        self.inner.lock().unwrap().events.push(Event::Copy);
+    }
+
+    /// Cuts the selection of the focused egui widget on the next frame
+    ///
+    /// Works like [`EguiState::handle_copy`], but also removes the selected text.
+    pub fn handle_cut(&self) {
+        self.inner.lock().unwrap().events.push(Event::Cut);
+    }
+
+    /// Sets if touch input should also be passed to egui as synthetic pointer events
+    ///
+    /// This is enabled by default for compatibility, as most egui widgets only react to the pointer.
+    /// Disable it, if your widgets handle multi-touch natively (e.g. a two-finger map pan).
+    pub fn set_touch_pointer_emulation(&self, enabled: bool) {
+        self.inner.lock().unwrap().touch_pointer_emulation = enabled;
+    }
+
+    /// Sets a factor, positions passed to [`EguiState::handle_pointer_motion`] are multiplied with
+    ///
+    /// Use this to correct for input devices, whose coordinate space doesn't match the display,
+    /// independently of the scale egui is rendered at. The default is `1.0`.
+    pub fn set_pointer_scale(&self, scale: f64) {
+        self.inner.lock().unwrap().pointer_scale = scale;
+    }
+
+    /// Sets if consecutive pointer motions between two frames should be merged into one
+    ///
+    /// This is enabled by default. Disable it, if every motion sample needs to reach egui,
+    /// e.g. for high-fidelity stroke capture in a drawing app.
+    pub fn set_coalesce_motion(&self, enabled: bool) {
+        self.inner.lock().unwrap().coalesce_motion = enabled;
+    }
+
+    /// Sets a distance in logical pixels, pointer motions shorter than are ignored
+    ///
+    /// Stabilizes hovering and long-presses on noisy input devices, like resistive touchscreens,
+    /// which report jitter while the pointer is held still. The default of `0.0` disables this.
+    pub fn set_motion_dead_zone(&self, px: f32) {
+        self.inner.lock().unwrap().motion_dead_zone = px;
     }
 
     /// Pass pointer button presses to `EguiState`
@@ -230,252 +1182,1218 @@ impl EguiState {
     ///       if there is an egui-element below your pointer.
     pub fn handle_pointer_button(&self, button: MouseButton, pressed: bool) {
         if let Some(button) = convert_button(button) {
-            let mut inner = self.inner.lock().unwrap();
-            let last_pos = inner.last_pointer_position;
-            let modifiers = convert_modifiers(inner.last_modifiers);
-            inner.events.push(Event::PointerButton {
-                pos: Pos2::new(last_pos.x as f32, last_pos.y as f32),
-                button,
-                pressed,
-                modifiers,
-            })
+            self.inner
+                .lock()
+                .unwrap()
+                .push_pointer_button(button, pressed);
         }
     }
 
+    /// Returns true, if a scroll area of egui actually moved in response to scrolling during the last [`EguiState::render`] call.
+    ///
+    /// If this is false after forwarding scroll events to egui, nothing scrollable was below the pointer,
+    /// so you may want to forward the scroll events to the client below instead.
+    pub fn scroll_consumed_last_frame(&self) -> bool {
+        self.inner.lock().unwrap().scroll_consumed
+    }
+
+    /// Returns true, if the last [`EguiState::render`] call uploaded any texture data, like a (re)built font atlas.
+    ///
+    /// Texture uploads can be large, so you may want to defer other heavy uploads to the next frame.
+    pub fn uploaded_textures_last_frame(&self) -> bool {
+        self.inner.lock().unwrap().uploaded_textures
+    }
+
+    /// Returns the damage of every render since the last call and clears it
+    ///
+    /// Use this, if presenting is decoupled from rendering egui, to get the combined damage
+    /// of all frames that were rendered in the meantime.
+    /// The rects are in the same space as the `area` passed to [`EguiState::render`].
+    pub fn take_accumulated_damage(&self) -> Vec<Rectangle<i32, Logical>> {
+        std::mem::take(&mut self.inner.lock().unwrap().accumulated_damage)
+    }
+
+    /// Tell egui the pointer entered its area at `position`
+    ///
+    /// The position is always sent to egui, even within the dead zone set by
+    /// [`EguiState::set_motion_dead_zone`], so the hover state is correct without waiting for further motion.
+    pub fn pointer_enter(&self, position: Point<f64, Logical>) {
+        self.inner.lock().unwrap().raw_pointer_position = None;
+        self.handle_pointer_motion(position.to_i32_round());
+    }
+
+    /// Tell egui the pointer is gone, e.g. because it was confined to another surface
+    ///
+    /// This clears the hover state of egui on the next frame.
+    pub fn pointer_gone(&self) {
+        self.inner.lock().unwrap().push_pointer_gone();
+    }
+
     /// Pass a pointer axis scrolling to `EguiState`
     ///
     /// Note: If you are unsure about *which* PointerAxisEvents to send to smithay-egui
     ///       instead of normal clients, check [`EguiState::wants_pointer`] to figure out,
     ///       if there is an egui-element below your pointer.
+    ///
+    /// While the modifier set by [`EguiState::set_zoom_modifier`] is held, scrolling zooms instead.
     pub fn handle_pointer_axis(&self, x_amount: f64, y_amount: f64) {
+        self.push_scroll(
+            egui::MouseWheelUnit::Point,
+            Vec2::new(x_amount as f32, y_amount as f32),
+        );
+    }
+
+    /// Pass a discrete pointer axis scrolling (e.g. the notches of a mouse wheel) to `EguiState`
+    ///
+    /// Works like [`EguiState::handle_pointer_axis`], but the amounts are given in fractions of 120 per notch
+    /// (like smithay's `amount_v120`). egui scrolls by whole lines per notch for these
+    /// (see [`egui::Options::line_scroll_speed`]), while continuous scrolling keeps panning smoothly.
+    pub fn handle_pointer_axis_discrete(&self, x_v120: f64, y_v120: f64) {
+        self.push_scroll(
+            egui::MouseWheelUnit::Line,
+            Vec2::new(x_v120 as f32, y_v120 as f32) / 120.0,
+        );
+    }
+
+    /// Pass a whole frame of pointer axis events to `EguiState`
+    ///
+    /// All axes of the frame are sent to egui as one scroll (or zoom, see [`EguiState::handle_pointer_axis`]).
+    /// Wheels scroll by lines using the `v120` values, other sources like touchpads or unknown ones
+    /// scroll smoothly by points.
+    /// Axes that stopped don't scroll.
+    pub fn handle_axis_frame(&self, frame: &AxisFrame) {
+        let (unit, mut delta) = match frame.source {
+            Some(AxisSource::Wheel | AxisSource::WheelTilt) => (
+                egui::MouseWheelUnit::Line,
+                match frame.v120 {
+                    Some((x_v120, y_v120)) => Vec2::new(x_v120 as f32, y_v120 as f32) / 120.0,
+                    // libinput reports 15 degrees of rotation per notch by default
+                    None => Vec2::new(frame.axis.0 as f32, frame.axis.1 as f32) / 15.0,
+                },
+            ),
+            _ => (
+                egui::MouseWheelUnit::Point,
+                Vec2::new(frame.axis.0 as f32, frame.axis.1 as f32),
+            ),
+        };
+        if frame.stop.0 {
+            delta.x = 0.0;
+        }
+        if frame.stop.1 {
+            delta.y = 0.0;
+        }
+        // frames, that only stop a finger scroll, carry no motion
+        if delta != Vec2::ZERO {
+            // wayland scrolls down for positive values, egui up
+            self.push_scroll(unit, -delta);
+        }
+    }
+
+    fn push_scroll(&self, unit: egui::MouseWheelUnit, delta: Vec2) {
         let mut inner = self.inner.lock().unwrap();
-        let modifiers = convert_modifiers(inner.last_modifiers);
+        let mut modifiers = convert_modifiers(inner.last_modifiers);
+        if inner
+            .zoom_modifier
+            .is_some_and(|zoom_modifier| modifiers.contains(zoom_modifier))
+        {
+            let points = match unit {
+                egui::MouseWheelUnit::Line => {
+                    delta.y * self.ctx.options(|options| options.line_scroll_speed)
+                }
+                _ => delta.y,
+            };
+            // same curve egui uses for its own scroll-to-zoom
+            inner.events.push(Event::Zoom((points / 200.0).exp()));
+            return;
+        }
+
+        // egui would otherwise turn these into zoom on its own
+        modifiers.ctrl = false;
+        modifiers.command = false;
+        modifiers.mac_cmd = false;
         inner.events.push(Event::MouseWheel {
-            unit: egui::MouseWheelUnit::Point,
-            delta: Vec2 {
-                x: x_amount as f32,
-                y: y_amount as f32,
-            },
+            unit,
+            delta,
             modifiers,
         })
     }
 
+    /// Sets the modifiers, that turn scrolling into zooming in [`EguiState::handle_pointer_axis`]
+    ///
+    /// The default is `Some(Modifiers::CTRL)`, `None` disables scroll-zoom entirely.
+    pub fn set_zoom_modifier(&self, modifier: Option<egui::Modifiers>) {
+        self.inner.lock().unwrap().zoom_modifier = modifier;
+    }
+
+    /// Confines the pointer position egui sees to the given region
+    ///
+    /// This is useful to keep egui's pointer in sync with a compositor-side pointer constraint,
+    /// e.g. to stop a drag from escaping a confined panel.
+    /// The region uses the same coordinate space as [`EguiState::handle_pointer_motion`].
+    ///
+    /// The default (`None`) confines the pointer to the full area.
+    pub fn set_pointer_region(&self, region: Option<Rectangle<i32, Logical>>) {
+        self.inner.lock().unwrap().pointer_region = region;
+    }
+
+    /// Rounds off the corners of the whole egui surface with the given radius in logical pixels
+    ///
+    /// Everything outside of the rounded rectangle spanning the area passed to [`EguiState::render`]
+    /// is rendered transparent. A radius of `0.0` (the default) disables the mask.
+    pub fn set_corner_radius(&self, radius: f32) {
+        self.inner.lock().unwrap().effects.corner_radius = radius.max(0.0);
+    }
+
+    /// Shows egui's built-in debug windows on top of every rendered frame
+    ///
+    /// Useful to inspect an embedded ui during development without modifying the `ui` closure.
+    /// All windows are hidden by default.
+    pub fn show_debug_windows(&self, which: DebugWindows) {
+        self.inner.lock().unwrap().debug_windows = which;
+    }
+
+    /// Applies a color transformation to the whole egui surface
+    ///
+    /// Use this to tint egui consistently with a global color transform of the compositor,
+    /// like a night-light filter. `None` (the default) disables the transformation.
+    pub fn set_color_transform(&self, transform: Option<ColorTransform>) {
+        self.inner.lock().unwrap().effects.color_transform = transform;
+    }
+
+    /// De-emphasizes the whole egui surface, e.g. while it is inactive behind a modal dialog
+    ///
+    /// Every pixel is desaturated to `saturation` (`0.0` is grayscale) and then multiplied with `tint`,
+    /// without the ui having to change. `Color32::WHITE` and `1.0` (the default) leave egui untouched.
+    pub fn set_tint(&self, tint: Color32, saturation: f32) {
+        let saturation = saturation.max(0.0);
+        self.inner.lock().unwrap().effects.tint =
+            (tint != Color32::WHITE || saturation != 1.0).then_some((tint, saturation));
+    }
+
+    /// Sets the number of samples used to anti-alias the edges of shapes egui paints
+    ///
+    /// egui already feathers edges, but some aliasing may remain on low-dpi outputs.
+    /// `0` (the default) or `1` disables multisampling. The count is clamped to what the driver supports,
+    /// if multisampling isn't supported at all, it is disabled again.
+    pub fn set_msaa(&self, samples: u8) {
+        self.inner.lock().unwrap().msaa_samples = samples;
+    }
+
     /// Set if this [`EguiState`] should consider itself focused
+    ///
+    /// Gaining focus makes egui redraw its text cursor, losing focus cancels in-progress drags,
+    /// so widgets aren't left half-interacted with.
     pub fn set_focused(&self, focused: bool) {
-        self.inner.lock().unwrap().focused = focused;
+        let mut inner = self.inner.lock().unwrap();
+        if inner.focused == focused {
+            return;
+        }
+        inner.focused = focused;
+        inner.events.push(Event::WindowFocused(focused));
+        if focused {
+            self.ctx.request_repaint();
+        } else {
+            self.ctx.stop_dragging();
+        }
+    }
+
+    /// Pass a new touch point to `EguiState`
+    ///
+    /// `position` is relative to the area of egui and scaled and clamped
+    /// like for [`EguiState::handle_pointer_motion`].
+    /// Unless disabled via [`EguiState::set_touch_pointer_emulation`], the first finger also moves
+    /// and presses the pointer, so widgets without touch support can be tapped.
+    pub fn handle_touch_down(&self, slot: TouchSlot, position: Point<f64, Logical>) {
+        let mut inner = self.inner.lock().unwrap();
+        // slots get reused, so every touch point gets a new id
+        let id = egui::TouchId(inner.next_touch_id);
+        inner.next_touch_id += 1;
+        let touch_position = inner
+            .scale_position(position)
+            .constrain(inner.pointer_region().to_f64());
+        inner.touches.insert(slot, (id, touch_position));
+        inner.push_touch(id, egui::TouchPhase::Start, touch_position);
+
+        let emulate = inner.touch_pointer_emulation && inner.pointer_touch.is_none();
+        if !emulate {
+            return;
+        }
+        inner.pointer_touch = Some(slot);
+        drop(inner);
+        self.handle_pointer_motion(position.to_i32_round());
+        self.inner
+            .lock()
+            .unwrap()
+            .push_pointer_button(PointerButton::Primary, true);
+    }
+
+    /// Pass the motion of a touch point to `EguiState`
+    ///
+    /// `position` is relative to the area of egui, like for [`EguiState::handle_touch_down`].
+    pub fn handle_touch_motion(&self, slot: TouchSlot, position: Point<f64, Logical>) {
+        let mut inner = self.inner.lock().unwrap();
+        let touch_position = inner
+            .scale_position(position)
+            .constrain(inner.pointer_region().to_f64());
+        let Some((id, last_position)) = inner.touches.get_mut(&slot) else {
+            return;
+        };
+        *last_position = touch_position;
+        let id = *id;
+        inner.push_touch(id, egui::TouchPhase::Move, touch_position);
+
+        let emulate = inner.pointer_touch == Some(slot);
+        drop(inner);
+        if emulate {
+            self.handle_pointer_motion(position.to_i32_round());
+        }
     }
 
-    // TODO: touch inputs
+    /// Pass the end of a touch point to `EguiState`
+    pub fn handle_touch_up(&self, slot: TouchSlot) {
+        let mut inner = self.inner.lock().unwrap();
+        let Some((id, position)) = inner.touches.remove(&slot) else {
+            return;
+        };
+        inner.push_touch(id, egui::TouchPhase::End, position);
+        if inner.pointer_touch == Some(slot) {
+            inner.end_pointer_touch();
+        }
+    }
+
+    /// Cancel all active touch points of `EguiState`, e.g. because a compositor gesture took over
+    pub fn handle_touch_cancel(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        for (_, (id, position)) in std::mem::take(&mut inner.touches) {
+            inner.push_touch(id, egui::TouchPhase::Cancel, position);
+        }
+        inner.end_pointer_touch();
+    }
 
     /// Produce a new frame of egui. Returns a [`RenderElement`]
     ///
     /// - `ui` is your drawing function
-    /// - `renderer` is a [`GlowRenderer`]
-    /// - `area` limits the space egui will be using and offsets the result
+    /// - `renderer` is a [`GlowRenderer`](smithay::backend::renderer::glow::GlowRenderer) or a [`GlesRenderer`]
+    /// - `area` limits the space egui will be using and offsets the result,
+    ///   everything egui paints (including window shadows and popups) is clipped to it
     /// - `scale` is the scale egui should render in
     /// - `alpha` applies (additional) transparency to the whole ui
     /// - `start_time` need to be a fixed point in time before the first `run` call to measure animation-times and the like.
     /// - `modifiers` should be the current state of modifiers pressed on the keyboards.
+    ///
+    /// If egui requests the pass to be discarded (see [`Context::request_discard`]), e.g. because an auto-sized
+    /// window was laid out at the wrong size, `ui` is run again before anything is painted,
+    /// up to [`EguiState::set_max_passes`] times.
+    ///
+    /// Only one buffer is kept per `EguiState` and it is recreated, whenever the size or scale changes.
+    /// To show the same ui on multiple outputs, use [`EguiState::render_for_output`]
+    /// or [`EguiState::clone_for_output`] instead, which keep one buffer per output.
+    ///
+    /// The returned element keeps its id across frames and reports only the parts of the buffer,
+    /// that were repainted, as its damage. So a damage tracker (e.g. smithay's `OutputDamageTracker`)
+    /// treats unchanged parts of the ui as unchanged, without splitting it into multiple elements.
+    ///
+    /// Custom painting with [`egui::PaintCallback`] is supported for callbacks of type [`egui_glow::CallbackFn`],
+    /// which are called with the glow context of the renderer, while the buffer of egui is bound.
+    /// Other callbacks are skipped with a warning.
+    ///
+    /// Returns [`EguiError::InvalidScale`] if `scale` is not a positive number.
     pub fn render(
         &self,
         ui: impl FnMut(&Context),
-        renderer: &mut GlowRenderer,
+        renderer: &mut impl BorrowMut<GlesRenderer>,
         area: Rectangle<i32, Logical>,
         scale: f64,
         alpha: f32,
-    ) -> Result<TextureRenderElement<GlesTexture>, GlesError> {
-        let int_scale = scale.ceil() as i32;
-        let user_data = renderer.egl_context().user_data();
-        if user_data.get::<UserDataType>().is_none() {
-            let painter = {
-                let mut frame = renderer.render(
-                    area.size.to_physical(int_scale),
-                    smithay::utils::Transform::Normal,
-                )?;
-                frame
-                    .with_context(|context| Painter::new(context.clone(), "", None, false))?
-                    .map_err(|_| GlesError::ShaderCompileError)?
-            };
-            renderer.egl_context().user_data().insert_if_missing(|| {
-                UserDataType::new(RefCell::new(GlState {
-                    painter,
-                    render_buffers: HashMap::new(),
-                    #[cfg(feature = "image")]
-                    images: HashMap::new(),
-                }))
-            });
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        self.render_scaled(ui, renderer, area, Scale::from(scale), alpha)
+    }
+
+    /// Produce a new frame of egui with independent horizontal and vertical scale factors.
+    ///
+    /// Works like [`EguiState::render`], but accepts an anisotropic `scale`.
+    /// egui itself has no notion of anisotropic scaling, so it lays out and tessellates
+    /// at the geometric mean of both factors. The underlying buffer is allocated with the
+    /// resolution of each factor (rounded up) and the tessellated ui is stretched to fill it,
+    /// so each axis is painted at its own resolution.
+    pub fn render_scaled(
+        &self,
+        ui: impl FnMut(&Context),
+        renderer: &mut impl BorrowMut<GlesRenderer>,
+        area: Rectangle<i32, Logical>,
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        self.render_internal(
+            ui,
+            renderer.borrow_mut(),
+            area,
+            scale,
+            alpha,
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Produce a new frame of egui, only repainting the given `damage`.
+    ///
+    /// Works like [`EguiState::render`], but restricts painting to `damage`
+    /// (relative to `area`) and reuses the previous contents of the underlying buffer everywhere else.
+    /// Use this, if you already know only a small part of the ui changed, e.g. a tooltip appeared.
+    ///
+    /// The whole `area` is still painted, if the underlying buffer had to be (re-)created.
+    pub fn render_damaged(
+        &self,
+        damage: &[Rectangle<i32, Logical>],
+        ui: impl FnMut(&Context),
+        renderer: &mut impl BorrowMut<GlesRenderer>,
+        area: Rectangle<i32, Logical>,
+        scale: f64,
+        alpha: f32,
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        self.render_internal(
+            ui,
+            renderer.borrow_mut(),
+            area,
+            Scale::from(scale),
+            alpha,
+            Some(damage),
+            None,
+            None,
+        )
+    }
+
+    /// Produce a new frame of egui for the given `output`.
+    ///
+    /// Works like [`EguiState::render`], but uses the current scale of `output`
+    /// and keeps a separate buffer for every output this is called with.
+    /// Use this, if the same `EguiState` is shown on multiple outputs,
+    /// to avoid re-creating the underlying buffer whenever rendering switches between them.
+    ///
+    /// egui lays out at the highest scale of all outputs this was called with,
+    /// so the layout stays the same on every output, while each output is still painted at its own scale.
+    ///
+    /// Buffers of destroyed outputs are cleaned up automatically.
+    pub fn render_for_output(
+        &self,
+        output: &Output,
+        ui: impl FnMut(&Context),
+        renderer: &mut impl BorrowMut<GlesRenderer>,
+        area: Rectangle<i32, Logical>,
+        alpha: f32,
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        let scale = Scale::from(output.current_scale().fractional_scale());
+        self.render_internal(
+            ui,
+            renderer.borrow_mut(),
+            area,
+            scale,
+            alpha,
+            None,
+            Some(output),
+            None,
+        )
+    }
+
+    /// Produce a new frame of egui using the given `style` for this frame only.
+    ///
+    /// Works like [`EguiState::render`], but temporarily replaces the style of the [`Context`]
+    /// and restores the previous one afterwards, e.g. to render a warning dialog in a different theme.
+    /// The swap is synchronized with other `render` calls, so clones sharing the context are not affected.
+    pub fn render_with_style(
+        &self,
+        style: egui::Style,
+        ui: impl FnMut(&Context),
+        renderer: &mut impl BorrowMut<GlesRenderer>,
+        area: Rectangle<i32, Logical>,
+        scale: f64,
+        alpha: f32,
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        self.render_internal(
+            ui,
+            renderer.borrow_mut(),
+            area,
+            Scale::from(scale),
+            alpha,
+            None,
+            None,
+            Some(style),
+        )
+    }
+
+    /// Produce a new frame of egui and warn, if it took longer than `budget`
+    ///
+    /// Works like [`EguiState::render`], but measures how long running `ui`, tessellating and painting took.
+    /// Rendering is never aborted, but if the whole frame exceeds `budget`, a warning with the time of every step
+    /// is logged, to find overlays causing frame drops.
+    pub fn render_with_budget(
+        &self,
+        budget: Duration,
+        ui: impl FnMut(&Context),
+        renderer: &mut impl BorrowMut<GlesRenderer>,
+        area: Rectangle<i32, Logical>,
+        scale: f64,
+        alpha: f32,
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        let start = Instant::now();
+        let result = self.render_internal(
+            ui,
+            renderer.borrow_mut(),
+            area,
+            Scale::from(scale),
+            alpha,
+            None,
+            None,
+            None,
+        );
+        let elapsed = start.elapsed();
+        if elapsed > budget {
+            let inner = self.inner.lock().unwrap();
+            let timings = inner.frame_timings;
+            log::warn!(
+                "Rendering {} took {:?}, exceeding its budget of {:?} (run: {:?}, tessellate: {:?}, paint: {:?})",
+                inner.label(),
+                elapsed,
+                budget,
+                timings.run,
+                timings.tessellate,
+                timings.paint,
+            );
+        }
+        result
+    }
+
+    /// Draw an `element` returned by [`EguiState::render`] (or any of its variants) into `frame`
+    ///
+    /// - `scale` should be the scale of the output `frame` is rendering to
+    /// - `damage` is the damage of the whole frame, drawing is clipped to it
+    ///
+    /// This takes care of passing the correct source and geometry of the element.
+    pub fn draw_to_frame<'frame>(
+        &self,
+        frame: &mut impl BorrowMut<GlesFrame<'frame>>,
+        element: &TextureRenderElement<GlesTexture>,
+        scale: f64,
+        damage: &[Rectangle<i32, Physical>],
+    ) -> Result<(), EguiError> {
+        let dst = element.geometry(Scale::from(scale));
+        let damage = damage
+            .iter()
+            .filter_map(|rect| rect.intersection(dst))
+            .map(|rect| {
+                // the element expects its damage relative to its own position
+                Rectangle::new(rect.loc - dst.loc, rect.size)
+            })
+            .collect::<Vec<_>>();
+        RenderElement::<GlesRenderer>::draw(
+            element,
+            frame.borrow_mut(),
+            element.src(),
+            dst,
+            &damage,
+            &[],
+        )?;
+        Ok(())
+    }
+
+    /// Begin a new egui pass and return the [`Context`] to draw into.
+    ///
+    /// This is a lower-level alternative to [`EguiState::render`], mirroring [`Context::begin_pass`].
+    /// Any egui APIs may be called on the returned context until the pass is finished with [`EguiState::end_pass`].
+    /// In the meantime input can still be passed to this `EguiState` and its settings changed,
+    /// it is picked up by the next pass. Methods running a pass of their own must not be called,
+    /// i.e. [`EguiState::render`] and its variants or [`EguiState::begin_pass`] again.
+    ///
+    /// The pass uses the area and scale of the previously rendered frame for its layout.
+    /// Unlike [`EguiState::render`], passes requested to be discarded by egui are not re-run.
+    pub fn begin_pass(&self) -> Context {
+        let input = {
+            let mut inner = self.inner.lock().unwrap();
+            debug_assert!(
+                inner.pass_scroll_delta.is_none(),
+                "a pass started by `EguiState::begin_pass` is still open"
+            );
+            let (area, pixels_per_point, max_texture_side) =
+                (inner.area, inner.pixels_per_point, inner.max_texture_side);
+            self.take_input(&mut inner, area, pixels_per_point, max_texture_side)
+        };
+        self.ctx.begin_pass(input);
+        self.inner.lock().unwrap().pass_scroll_delta =
+            Some(self.ctx.input(|i| i.smooth_scroll_delta));
+        self.ctx.clone()
+    }
+
+    /// Finish the pass started by [`EguiState::begin_pass`] and render it. Returns a [`RenderElement`]
+    ///
+    /// Takes the same arguments as [`EguiState::render`] (minus the `ui` closure).
+    ///
+    /// Returns [`EguiError::InvalidScale`] if `scale` is not a positive number.
+    /// The pass is finished and its output kept in any case, so [`EguiState::begin_pass`] can be called again.
+    pub fn end_pass(
+        &self,
+        renderer: &mut impl BorrowMut<GlesRenderer>,
+        area: Rectangle<i32, Logical>,
+        scale: f64,
+        alpha: f32,
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        let own_output = self.output.as_ref().and_then(WeakOutput::upgrade);
+        let scale = output_scale(own_output.as_ref(), Scale::from(scale));
+        let debug_windows = self.inner.lock().unwrap().debug_windows;
+        debug_windows.show(&self.ctx);
+        #[cfg(feature = "desktop_integration")]
+        let background = background_region(&self.ctx);
+
+        let scroll_after = self.ctx.input(|i| i.smooth_scroll_delta);
+        let full_output = self.ctx.end_pass();
+        let mut inner = self.inner.lock().unwrap();
+        #[cfg(feature = "desktop_integration")]
+        {
+            inner.background_region = background;
+        }
+        inner.scroll_consumed = inner
+            .pass_scroll_delta
+            .take()
+            .is_some_and(|scroll_before| scroll_consumed(scroll_before, scroll_after));
+        // the pass needs to be finished in any case, or the next one would be nested into it
+        let ready = validate_scale(scale).and_then(|_| match inner.suspended {
+            true => Err(EguiError::Suspended),
+            false => Ok(()),
+        });
+        if let Err(err) = ready {
+            self.skip_paint(&mut inner, full_output);
+            return Err(err);
+        }
+        self.paint(
+            &mut inner,
+            full_output,
+            renderer.borrow_mut(),
+            area,
+            scale,
+            alpha,
+            None,
+            own_output.as_ref(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_internal(
+        &self,
+        ui: impl FnMut(&Context),
+        renderer: &mut GlesRenderer,
+        area: Rectangle<i32, Logical>,
+        scale: Scale<f64>,
+        alpha: f32,
+        damage: Option<&[Rectangle<i32, Logical>]>,
+        output: Option<&Output>,
+        style: Option<egui::Style>,
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        let own_output = self.output.as_ref().and_then(WeakOutput::upgrade);
+        let output = output.or(own_output.as_ref());
+        let scale = output_scale(output, scale);
+        validate_scale(scale)?;
+        // input keeps queuing up for the first frame after resuming
+        if self.inner.lock().unwrap().suspended {
+            return Err(EguiError::Suspended);
         }
 
+        let gl_state = gl_state(renderer)?;
+        let max_texture_side = gl_state.borrow().painter.max_texture_side();
         let mut inner = self.inner.lock().unwrap();
-        let gl_state = renderer
-            .egl_context()
-            .user_data()
-            .get::<UserDataType>()
-            .unwrap()
-            .clone();
-        let mut borrow = gl_state.borrow_mut();
-        let &mut GlState {
-            ref mut painter,
-            ref mut render_buffers,
-            ..
-        } = &mut *borrow;
+        debug_assert!(
+            inner.pass_scroll_delta.is_none(),
+            "rendering while a pass started by `EguiState::begin_pass` is still open"
+        );
+        let layout_pixels_per_point = match output {
+            // lay out at the same scale for every output, otherwise the layout would change
+            // whenever rendering switches between outputs of different scales
+            Some(output) => {
+                inner.output_scales.retain(|output, _| output.is_alive());
+                inner
+                    .output_scales
+                    .insert(output.downgrade(), pixels_per_point(scale));
+                inner
+                    .output_scales
+                    .values()
+                    .copied()
+                    .fold(f32::MIN, f32::max)
+            }
+            None => pixels_per_point(scale),
+        };
+        let full_output = self.run(
+            &mut inner,
+            ui,
+            area,
+            layout_pixels_per_point,
+            Some(max_texture_side),
+            style,
+        );
+
+        let paint_start = Instant::now();
+        let result = self.paint(
+            &mut inner,
+            full_output,
+            renderer,
+            area,
+            scale,
+            alpha,
+            damage,
+            output,
+        );
+        inner.frame_timings.paint = paint_start
+            .elapsed()
+            .saturating_sub(inner.frame_timings.tessellate);
+        result
+    }
 
-        let render_buffer = render_buffers.entry(self.id()).or_insert_with(|| {
-            let render_texture = renderer
-                .create_buffer(
-                    Fourcc::Abgr8888,
-                    area.size
-                        .to_buffer(int_scale, smithay::utils::Transform::Normal),
-                )
-                .expect("Failed to create buffer");
-            TextureRenderBuffer::from_texture(
-                renderer,
-                render_texture,
-                int_scale,
-                Transform::Flipped180,
-                None,
-            )
+    // runs `ui` with the queued input, without touching the renderer
+    fn run(
+        &self,
+        inner: &mut EguiInner,
+        mut ui: impl FnMut(&Context),
+        area: Rectangle<i32, Logical>,
+        pixels_per_point: f32,
+        max_texture_side: Option<usize>,
+        style: Option<egui::Style>,
+    ) -> FullOutput {
+        let input = self.take_input(inner, area, pixels_per_point, max_texture_side);
+
+        let debug_windows = inner.debug_windows;
+        let previous_style = style.map(|style| {
+            let previous = self.ctx.style();
+            self.ctx.set_style(style);
+            previous
+        });
+        let mut scroll_was_consumed = false;
+        #[cfg(feature = "desktop_integration")]
+        let mut background = Vec::new();
+        let run_start = Instant::now();
+        let full_output = self.ctx.run(input, |ctx| {
+            let scroll_before = ctx.input(|i| i.smooth_scroll_delta);
+            ui(ctx);
+            let scroll_after = ctx.input(|i| i.smooth_scroll_delta);
+            scroll_was_consumed = scroll_consumed(scroll_before, scroll_after);
+            debug_windows.show(ctx);
+            #[cfg(feature = "desktop_integration")]
+            {
+                background = background_region(ctx);
+            }
         });
+        if let Some(previous) = previous_style {
+            self.ctx.set_style(previous);
+        }
+        inner.scroll_consumed = scroll_was_consumed;
+        #[cfg(feature = "desktop_integration")]
+        {
+            inner.background_region = background;
+        }
+        inner.frame_timings.run = run_start.elapsed();
+        inner.frame_timings.tessellate = Duration::ZERO;
+        full_output
+    }
+
+    // keeps what egui reported for a pass, that is not painted
+    fn skip_paint(&self, inner: &mut EguiInner, full_output: FullOutput) {
+        self.apply_output(
+            inner,
+            full_output.platform_output,
+            full_output.viewport_output,
+        );
+        // the painter would miss e.g. the glyphs added to the font atlas otherwise
+        merge_textures(&mut inner.pending_textures, full_output.textures_delta);
+    }
+
+    // stores the output of a pass, that doesn't need the renderer, returns the requested screenshots
+    fn apply_output(
+        &self,
+        inner: &mut EguiInner,
+        platform_output: PlatformOutput,
+        viewport_output: ViewportIdMap<ViewportOutput>,
+    ) -> Vec<(ViewportId, egui::UserData)> {
+        inner.cursor_icon = platform_output.cursor_icon;
+        inner.mutable_text_under_cursor = platform_output.mutable_text_under_cursor;
+        // egui only requests ime output, while a text field is focused
+        inner.text_input_active = platform_output.ime.is_some();
+        inner.last_output = Some(platform_output);
+        let screenshot_requests = viewport_output
+            .iter()
+            .flat_map(|(id, output)| {
+                output
+                    .commands
+                    .iter()
+                    .filter_map(move |command| match command {
+                        egui::ViewportCommand::Screenshot(user_data) => {
+                            Some((*id, user_data.clone()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect::<Vec<_>>();
+        inner.requested_repaints = viewport_output
+            .into_iter()
+            .filter(|(_, output)| output.repaint_delay != Duration::MAX)
+            .map(|(id, output)| (id, output.repaint_delay.max(inner.min_repaint_interval)))
+            .collect();
+
+        let wants_keyboard = self.ctx.wants_keyboard_input();
+        inner.soft_keyboard_changed =
+            (wants_keyboard != inner.wants_keyboard).then_some(wants_keyboard);
+        inner.wants_keyboard = wants_keyboard;
+        screenshot_requests
+    }
 
-        let screen_size: Size<i32, Physical> = area.size.to_physical(int_scale);
+    fn take_input(
+        &self,
+        inner: &mut EguiInner,
+        area: Rectangle<i32, Logical>,
+        pixels_per_point: f32,
+        max_texture_side: Option<usize>,
+    ) -> RawInput {
+        if inner
+            .hold_start
+            .is_some_and(|start| !inner.hold_pressed && start.elapsed() >= LONG_PRESS_DELAY)
+        {
+            inner.push_pointer_button(PointerButton::Secondary, true);
+            inner.hold_pressed = true;
+        }
+        inner.dispatch_repeat(Instant::now());
+        // the queued key events are handed to egui now, a later modifier update is too late for them
+        inner.unsynced_key_events = 0;
+
+        let mut viewports = ViewportIdMap::default();
+        viewports.insert(
+            ViewportId::ROOT,
+            ViewportInfo {
+                native_pixels_per_point: Some(pixels_per_point),
+                ..Default::default()
+            },
+        );
         let input = RawInput {
+            viewports,
             screen_rect: Some(Rect {
                 min: Pos2 { x: 0.0, y: 0.0 },
                 max: Pos2 {
-                    x: screen_size.w as f32,
-                    y: screen_size.h as f32,
+                    x: area.size.w as f32,
+                    y: area.size.h as f32,
                 },
             }),
             time: Some(self.start_time.elapsed().as_secs_f64()),
             modifiers: convert_modifiers(inner.last_modifiers),
             events: inner.events.drain(..).collect(),
             focused: inner.focused,
-            max_texture_side: Some(painter.max_texture_side()), // TODO query from GlState somehow
+            max_texture_side,
+            system_theme: inner.system_theme,
             ..Default::default()
         };
+        #[cfg(feature = "debug")]
+        {
+            inner.last_raw_input = Some(input.clone());
+        }
+        input
+    }
 
+    #[allow(clippy::too_many_arguments)]
+    fn paint(
+        &self,
+        inner: &mut EguiInner,
+        full_output: FullOutput,
+        renderer: &mut GlesRenderer,
+        area: Rectangle<i32, Logical>,
+        scale: Scale<f64>,
+        alpha: f32,
+        damage: Option<&[Rectangle<i32, Logical>]>,
+        output: Option<&Output>,
+    ) -> Result<TextureRenderElement<GlesTexture>, EguiError> {
+        inner.uploaded_textures = false;
         let FullOutput {
             platform_output,
             shapes,
-            textures_delta,
-            ..
-        } = self.ctx.run(input.clone(), ui);
-        inner.last_output = Some(platform_output);
+            textures_delta: new_textures,
+            pixels_per_point: layout_pixels_per_point,
+            viewport_output,
+        } = full_output;
+        let screenshot_requests = self.apply_output(inner, platform_output, viewport_output);
+        let mut textures_delta = std::mem::take(&mut inner.pending_textures);
+        merge_textures(&mut textures_delta, new_textures);
+        let buffer_scale = buffer_scale(scale);
+        // smithay describes buffers with a single integer scale, so anisotropic buffers are stretched
+        // by their render element instead. smithay only supports that for buffers in its own orientation.
+        let anisotropic = buffer_scale.x != buffer_scale.y;
+        let buffer_transform = match anisotropic {
+            true => Transform::Normal,
+            false => Transform::Flipped180,
+        };
+        let pixels_per_point = pixels_per_point(scale);
 
-        let needs_recreate = inner.area != area;
-        inner.area = area;
+        let gl_state = gl_state(renderer)?;
+        let mut borrow = RefCell::borrow_mut(&gl_state);
+        let &mut GlState {
+            generation,
+            ref mut painter,
+            ref mut render_buffers,
+            ref mut effects_program,
+            ref mut effect_buffers,
+            ref mut msaa_buffers,
+            ..
+        } = &mut *borrow;
+        // don't blame errors of the compositor on the steps below
+        #[cfg(feature = "gl_debug")]
+        renderer.with_context(|_| clear_gl_errors(painter.gl()))?;
+        inner.pixels_per_point = pixels_per_point;
+        inner.max_texture_side = Some(painter.max_texture_side());
+        // the gpu state was rebuilt by resuming (possibly another `EguiState` sharing the renderer),
+        // so the textures uploaded before are gone
+        if inner
+            .gl_generation
+            .is_some_and(|uploaded| uploaded != generation)
+        {
+            let font_image = self.ctx.fonts(|fonts| fonts.image());
+            textures_delta.set.insert(
+                0,
+                (
+                    TextureId::default(),
+                    ImageDelta::full(font_image, TextureAtlas::texture_options()),
+                ),
+            );
+            self.ctx.forget_all_images();
+        }
+        inner.gl_generation = Some(generation);
+
+        // drop the buffers of outputs, that are gone
+        render_buffers.retain(|(_, output), _| output.as_ref().is_none_or(WeakOutput::is_alive));
+        effect_buffers.retain(|(_, output), _| output.as_ref().is_none_or(WeakOutput::is_alive));
+        // multisampled buffers can only be dropped while painting, as that makes the gl context current
+        let invalidate_buffers = std::mem::take(&mut inner.invalidate_buffers);
+        if invalidate_buffers {
+            let id = self.id();
+            render_buffers.retain(|(buffer_id, _), _| *buffer_id != id);
+            effect_buffers.retain(|(buffer_id, _), _| *buffer_id != id);
+        }
 
+        let key = (self.id(), output.map(Output::downgrade));
+        let needs_recreate = render_buffers
+            .get(&key)
+            .is_none_or(|buffer| buffer.size != area.size || buffer.scale != buffer_scale);
         if needs_recreate {
-            *render_buffer = {
-                let render_texture = renderer.create_buffer(
+            let render_texture = renderer
+                .create_buffer(
                     Fourcc::Abgr8888,
                     area.size
-                        .to_buffer(int_scale, smithay::utils::Transform::Normal),
-                )?;
-                TextureRenderBuffer::from_texture(
-                    renderer,
-                    render_texture,
-                    int_scale,
-                    Transform::Flipped180,
-                    None,
+                        .to_buffer(buffer_scale, smithay::utils::Transform::Normal),
                 )
-            };
+                .map_err(EguiError::BufferAllocation)?;
+            check_gl_error(painter.gl(), "create buffer")?;
+            render_buffers.insert(
+                key.clone(),
+                RenderBuffer {
+                    buffer: TextureRenderBuffer::from_texture(
+                        renderer,
+                        render_texture,
+                        match anisotropic {
+                            true => 1,
+                            false => buffer_scale.x,
+                        },
+                        buffer_transform,
+                        None,
+                    ),
+                    size: area.size,
+                    scale: buffer_scale,
+                    used: None,
+                },
+            );
         }
+        let render_buffer = render_buffers.get_mut(&key).unwrap();
+        inner.area = area;
+        inner.last_paint = Some(Instant::now());
+        inner.repaint_deadline = None;
+
+        // egui is painted into an intermediate texture first, if any effects need to be applied on top,
+        // or the result needs to be flipped into smithay's orientation
+        let effects = inner.effects;
+        let previous_effects = effect_buffers.get(&key).map(|(_, effects)| *effects);
+        let intermediate = if effects.is_active() || anisotropic {
+            if effects_program.is_none() && effects.is_active() {
+                *effects_program = Some(effects::compile_program(renderer)?);
+            }
+            let buffer_size = area.size.to_buffer(buffer_scale, Transform::Normal);
+            match effect_buffers.get_mut(&key) {
+                Some((texture, applied)) if texture.size() == buffer_size => {
+                    *applied = effects;
+                    Some(texture.clone())
+                }
+                _ => {
+                    let texture: GlesTexture = renderer
+                        .create_buffer(Fourcc::Abgr8888, buffer_size)
+                        .map_err(EguiError::BufferAllocation)?;
+                    effect_buffers.insert(key.clone(), (texture.clone(), effects));
+                    Some(texture)
+                }
+            }
+        } else {
+            effect_buffers.remove(&key);
+            None
+        };
+        let effects_changed = previous_effects != intermediate.as_ref().map(|_| effects);
+
+        // everything egui painted last frame lies within `previous_used`, the rest of the buffer is transparent
+        let used =
+            used_rect(&shapes).and_then(|used| used.intersection(Rectangle::from_size(area.size)));
+        let previous_used = render_buffer.used;
+        render_buffer.used = match damage {
+            // parts of the previous frame outside of `damage` are kept around
+            Some(_) => match (previous_used, used) {
+                (Some(previous), Some(used)) => Some(previous.merge(used)),
+                (previous, used) => previous.or(used),
+            },
+            None => used,
+        };
 
-        render_buffer.render().draw(|tex| {
-            renderer.bind(tex.clone())?;
-            let physical_area = area.to_physical(int_scale);
+        // a new buffer has no previous contents to reuse
+        let damage = Some(damage.map_or_else(
+            || previous_used.into_iter().chain(used).collect::<Vec<_>>(),
+            <[_]>::to_vec,
+        ))
+        .filter(|_| !(needs_recreate || effects_changed))
+        .map(|damage| {
+            // split into non-overlapping rects, so nothing gets blended twice
+            let mut disjoint = Vec::<Rectangle<i32, Logical>>::new();
+            for rect in damage
+                .iter()
+                .filter_map(|rect| rect.intersection(Rectangle::from_size(area.size)))
+            {
+                let remaining = rect.subtract_rects(disjoint.iter().copied());
+                disjoint.extend(remaining);
+            }
+            disjoint
+        });
+
+        // everything repainted by this frame
+        let painted = damage
+            .clone()
+            .unwrap_or_else(|| vec![Rectangle::from_size(area.size)]);
+        let msaa_samples = inner.msaa_samples;
+        let label = inner.label();
+        let mut msaa_unsupported = false;
+        let mut screenshots = Vec::new();
+        let mut tessellate_time = Duration::ZERO;
+        render_buffer.buffer.render().draw(|tex| {
+            renderer.bind(intermediate.clone().unwrap_or_else(|| tex.clone()))?;
+            check_gl_error(painter.gl(), "bind buffer")?;
+            let physical_area = Rectangle::from_size(area.size.to_physical(buffer_scale));
+            // the frames of smithay are upside down compared to what egui paints with gl
+            let physical_damage = match damage.as_ref() {
+                Some(damage) => damage
+                    .iter()
+                    .map(|rect| {
+                        Transform::Flipped180
+                            .transform_rect_in(rect.to_physical(buffer_scale), &physical_area.size)
+                    })
+                    .collect::<Vec<_>>(),
+                None => vec![physical_area],
+            };
             {
                 let mut frame = renderer.render(physical_area.size, Transform::Normal)?;
-                frame.clear([0.0, 0.0, 0.0, 0.0].into(), &[physical_area])?;
+                frame.clear([0.0, 0.0, 0.0, 0.0].into(), &physical_damage)?;
+
+                let gl = painter.gl().clone();
+                // SAFETY: `frame` keeps the egl context current
+                let msaa_buffer = unsafe {
+                    msaa::retain(&gl, msaa_buffers, |buffer_key, buffer| {
+                        buffer_key.1.as_ref().is_none_or(WeakOutput::is_alive)
+                            && !(invalidate_buffers && buffer_key.0 == key.0)
+                            && (*buffer_key != key
+                                || buffer.matches(physical_area.size, msaa_samples))
+                    });
+                    if msaa_samples > 1 && !msaa_buffers.contains_key(&key) {
+                        match msaa::MsaaBuffer::new(&gl, physical_area.size, msaa_samples, &label) {
+                            Some(buffer) => {
+                                msaa_buffers.insert(key.clone(), buffer);
+                            }
+                            None => msaa_unsupported = true,
+                        }
+                    }
+                    msaa_buffers.get(&key)
+                };
+
+                // text is only valid at the scale egui laid it out at
+                let tessellate_start = Instant::now();
+                let mut clipped_primitives = self.ctx.tessellate(shapes, layout_pixels_per_point);
+                tessellate_time = tessellate_start.elapsed();
+                // the multisampled buffer is cleared and resolved as a whole
+                if let Some(damage) = damage.as_ref().filter(|_| msaa_buffer.is_none()) {
+                    clipped_primitives = clip_to_damage(clipped_primitives, damage);
+                }
+                // egui_glow only supports a single scale, so anisotropic frames are painted in pixels
+                let paint_pixels_per_point = match anisotropic {
+                    true => {
+                        stretch_primitives(
+                            &mut clipped_primitives,
+                            Vec2::new(buffer_scale.x as f32, buffer_scale.y as f32),
+                        );
+                        1.0
+                    }
+                    false => buffer_scale.x as f32,
+                };
+                // SAFETY: `frame` keeps the egl context current
+                let target = msaa_buffer.map(|buffer| unsafe { buffer.bind(&gl) });
                 painter.paint_and_update_textures(
                     [physical_area.size.w as u32, physical_area.size.h as u32],
-                    int_scale as f32,
-                    &self.ctx.tessellate(shapes, int_scale as f32),
+                    paint_pixels_per_point,
+                    &clipped_primitives,
                     &textures_delta,
                 );
+                check_gl_error(&gl, "upload textures and paint")?;
+                // name the textures egui just created, to find them in gpu captures
+                if gl.supports_debug() {
+                    for (id, delta) in &textures_delta.set {
+                        let Some(texture) = painter.texture(*id).filter(|_| delta.pos.is_none())
+                        else {
+                            continue;
+                        };
+                        let label = match *id {
+                            TextureId::Managed(0) => format!("{} font atlas", label),
+                            id => format!("{} texture {:?}", label, id),
+                        };
+                        // SAFETY: `frame` keeps the egl context current
+                        unsafe { gl.object_label(glow::TEXTURE, texture.0.get(), Some(label)) };
+                    }
+                }
+                if let (Some(buffer), Some(target)) = (msaa_buffer, target) {
+                    // SAFETY: `frame` keeps the egl context current
+                    unsafe { buffer.resolve(&gl, target) };
+                    check_gl_error(&gl, "resolve msaa")?;
+                }
+            }
+            if let Some(intermediate) = intermediate.as_ref() {
+                let program = effects_program.as_ref().filter(|_| effects.is_active());
+                let uniforms = match program {
+                    Some(_) => effects.uniforms(
+                        physical_area.size,
+                        ((buffer_scale.x * buffer_scale.y) as f32).sqrt(),
+                    ),
+                    None => Vec::new(),
+                };
+                // the intermediate texture is always in the orientation of gl,
+                // so flipping it into smithay's orientation also flips the damage
+                let (src_transform, damage) = match anisotropic {
+                    true => (
+                        Transform::Flipped180,
+                        physical_damage
+                            .iter()
+                            .map(|rect| {
+                                Transform::Flipped180.transform_rect_in(*rect, &physical_area.size)
+                            })
+                            .collect(),
+                    ),
+                    false => (Transform::Normal, physical_damage.clone()),
+                };
+                renderer.bind(tex.clone())?;
+                let mut frame = renderer.render(physical_area.size, Transform::Normal)?;
+                frame.clear([0.0, 0.0, 0.0, 0.0].into(), &damage)?;
+                frame.render_texture_from_to(
+                    intermediate,
+                    Rectangle::from_size(intermediate.size()).to_f64(),
+                    physical_area,
+                    &damage,
+                    &[],
+                    src_transform,
+                    1.0,
+                    program,
+                    &uniforms,
+                )?;
+                check_gl_error(painter.gl(), "apply effects")?;
+            }
+            if !screenshot_requests.is_empty() {
+                // the final texture is still bound
+                let mut image = painter
+                    .read_screen_rgba([physical_area.size.w as u32, physical_area.size.h as u32]);
+                // egui_glow expects the buffer in the orientation of gl
+                if buffer_transform == Transform::Normal {
+                    image.pixels = image
+                        .pixels
+                        .chunks(image.size[0])
+                        .rev()
+                        .flatten()
+                        .copied()
+                        .collect();
+                }
+                let image = Arc::new(image);
+                check_gl_error(painter.gl(), "read screenshot")?;
+                screenshots.extend(screenshot_requests.into_iter().map(
+                    |(viewport_id, user_data)| Event::Screenshot {
+                        viewport_id,
+                        user_data,
+                        image: image.clone(),
+                    },
+                ));
             }
             renderer.unbind()?;
 
-            let used = self.ctx.used_rect();
-            let margin = self.ctx.style().visuals.clip_rect_margin.ceil() as i32;
-            let window_shadow = self
-                .ctx
-                .style()
-                .visuals
-                .window_shadow
-                .margin()
-                .sum()
-                .max_elem()
-                .ceil() as i32;
-            let popup_shadow = self
-                .ctx
-                .style()
-                .visuals
-                .popup_shadow
-                .margin()
-                .sum()
-                .max_elem()
-                .ceil() as i32;
-            let offset = margin + Ord::max(window_shadow, popup_shadow);
-            Result::<_, GlesError>::Ok(vec![Rectangle::<i32, Logical>::from_extemities(
-                (
-                    (used.min.x.floor() as i32).saturating_sub(offset),
-                    (used.min.y.floor() as i32).saturating_sub(offset),
-                ),
-                (
-                    (used.max.x.ceil() as i32) + (offset * 2),
-                    (used.max.y.ceil() as i32) + (offset * 2),
-                ),
+            Result::<_, EguiError>::Ok(
+                painted
+                    .iter()
+                    .map(|rect| rect.to_buffer(buffer_scale, buffer_transform, &area.size))
+                    .collect(),
             )
-            .to_buffer(int_scale, Transform::Flipped180, &area.size)])
         })?;
+        inner.frame_timings.tessellate = tessellate_time;
+        inner.uploaded_textures = !textures_delta.set.is_empty();
+        inner.accumulated_damage.extend(
+            painted
+                .into_iter()
+                .map(|rect| Rectangle::new(rect.loc + area.loc, rect.size)),
+        );
+        // keep the damage of a consumer, that never collects it, from growing forever
+        if inner.accumulated_damage.len() > MAX_ACCUMULATED_DAMAGE {
+            let bounds = inner
+                .accumulated_damage
+                .drain(..)
+                .reduce(|bounds, rect| bounds.merge(rect));
+            inner.accumulated_damage.extend(bounds);
+        }
+        // egui expects screenshots to be delivered with the input of the next frame
+        if !screenshots.is_empty() {
+            inner.events.extend(screenshots);
+            self.ctx.request_repaint();
+        }
+        if msaa_unsupported {
+            log::warn!(
+                "Multisampling is not supported, disabling msaa for {}.",
+                label
+            );
+            inner.msaa_samples = 0;
+        }
 
+        // the element stretches the whole buffer over the area
+        let (src, size) = match anisotropic {
+            true => (
+                Some(Rectangle::from_size(
+                    area.size.to_physical(buffer_scale).to_f64().to_logical(1.0),
+                )),
+                Some(area.size),
+            ),
+            false => (None, None),
+        };
         Ok(TextureRenderElement::from_texture_render_buffer(
             area.loc.to_f64().to_physical(scale),
-            &render_buffer,
+            &render_buffer.buffer,
             Some(alpha),
-            None,
-            None,
+            src,
+            size,
             Kind::Unspecified,
         ))
     }
 
+    /// Decodes the png or jpeg image `bytes` and keeps it under `name` for [`EguiState::with_image`]
     #[cfg(all(feature = "image", any(feature = "png", feature = "jpg")))]
     pub fn load_image(
         &self,
-        renderer: &mut GlowRenderer,
+        renderer: &mut impl BorrowMut<GlesRenderer>,
         name: String,
         bytes: &[u8],
     ) -> Result<(), String> {
-        let user_data = renderer.egl_context().user_data();
-        if user_data.get::<UserDataType>().is_none() {
-            let painter = {
-                let mut frame = renderer
-                    .render((1, 1).into(), smithay::utils::Transform::Normal)
-                    .map_err(|err| format!("{}", err))?;
-                frame
-                    .with_context(|context| Painter::new(context.clone(), "", None, false))
-                    .map_err(|err| format!("{}", err))?
-                    .map_err(|err| format!("{}", err))?
-            };
-            renderer.egl_context().user_data().insert_if_missing(|| {
-                UserDataType::new(RefCell::new(GlState {
-                    painter,
-                    render_buffers: HashMap::new(),
-                    #[cfg(feature = "image")]
-                    images: HashMap::new(),
-                }))
-            });
-        }
-
-        let gl_state = renderer
-            .egl_context()
-            .user_data()
-            .get::<UserDataType>()
-            .unwrap()
-            .clone();
-        let mut borrow = gl_state.borrow_mut();
+        let gl_state = gl_state(renderer.borrow_mut()).map_err(|err| format!("{}", err))?;
+        let mut borrow = RefCell::borrow_mut(&gl_state);
 
         let image = egui_extras::RetainedImage::from_image_bytes(name.clone(), bytes)?;
         borrow.images.insert(name, image);
@@ -483,41 +2401,16 @@ impl EguiState {
         Ok(())
     }
 
+    /// Rasterizes the svg image `bytes` and keeps it under `name` for [`EguiState::with_image`]
     #[cfg(all(feature = "image", feature = "svg"))]
     pub fn load_svg(
         &self,
-        renderer: &mut GlowRenderer,
+        renderer: &mut impl BorrowMut<GlesRenderer>,
         name: String,
         bytes: &[u8],
     ) -> Result<(), String> {
-        let user_data = renderer.egl_context().user_data();
-        if user_data.get::<UserDataType>().is_none() {
-            let painter = {
-                let mut frame = renderer
-                    .render((1, 1).into(), smithay::utils::Transform::Normal)
-                    .map_err(|err| format!("{}", err))?;
-                frame
-                    .with_context(|context| Painter::new(context.clone(), "", None, false))
-                    .map_err(|err| format!("{}", err))?
-                    .map_err(|err| format!("{}", err))?
-            };
-            renderer.egl_context().user_data().insert_if_missing(|| {
-                UserDataType::new(RefCell::new(GlState {
-                    painter,
-                    render_buffers: HashMap::new(),
-                    #[cfg(feature = "image")]
-                    images: HashMap::new(),
-                }))
-            });
-        }
-
-        let gl_state = renderer
-            .egl_context()
-            .user_data()
-            .get::<UserDataType>()
-            .unwrap()
-            .clone();
-        let mut borrow = gl_state.borrow_mut();
+        let gl_state = gl_state(renderer.borrow_mut()).map_err(|err| format!("{}", err))?;
+        let mut borrow = RefCell::borrow_mut(&gl_state);
 
         let image = egui_extras::RetainedImage::from_svg_bytes(name.clone(), bytes)?;
         borrow.images.insert(name, image);
@@ -525,18 +2418,33 @@ impl EguiState {
         Ok(())
     }
 
+    /// Calls `closure` with the image loaded under `name`, if any
     #[cfg(feature = "image")]
-    pub fn with_image<F, R>(&self, renderer: &mut GlowRenderer, name: &str, closure: F) -> Option<R>
+    pub fn with_image<F, R>(
+        &self,
+        renderer: &mut impl BorrowMut<GlesRenderer>,
+        name: &str,
+        closure: F,
+    ) -> Option<R>
     where
         F: FnOnce(&egui_extras::RetainedImage, &Context) -> R,
     {
-        let user_data = renderer.egl_context().user_data();
+        let user_data = renderer.borrow_mut().egl_context().user_data();
         let state = user_data.get::<UserDataType>()?;
         let state_ref = state.borrow();
         let img = state_ref.images.get(name)?;
         Some(closure(img, &self.ctx))
     }
 
+    /// Returns the [`RawInput`] passed to egui for the most recent frame.
+    ///
+    /// This includes the events, modifiers, screen_rect and time egui saw,
+    /// which is useful to log and diff, when diagnosing why a widget didn't respond.
+    #[cfg(feature = "debug")]
+    pub fn last_raw_input(&self) -> Option<RawInput> {
+        self.inner.lock().unwrap().last_raw_input.clone()
+    }
+
     /// Sets the z_index as reported by [`SpaceElement::z_index`].
     ///
     /// The default is [`RenderZindex::Overlay`].
@@ -545,12 +2453,525 @@ impl EguiState {
         self.inner.lock().unwrap().z_index = idx;
     }
 
+    /// Returns the outputs this [`EguiState`] currently overlaps, as reported by [`SpaceElement::output_enter`]
+    ///
+    /// Use this to send frame callbacks or pick the right scale for every output the overlay spans.
+    #[cfg(feature = "desktop_integration")]
+    pub fn outputs(&self) -> Vec<Output> {
+        self.inner
+            .lock()
+            .unwrap()
+            .outputs
+            .iter()
+            .filter_map(WeakOutput::upgrade)
+            .collect()
+    }
+
+    /// Returns the cursor icon egui requested during the last [`Self::render`] call
+    ///
+    /// Unlike [`Self::last_output`] this does not clone anything and is cheap to call every frame.
+    /// See [`convert_cursor_icon`] and [`egui_cursor_to_shape`] to map it to a cursor of the compositor.
+    pub fn cursor_icon(&self) -> CursorIcon {
+        self.inner.lock().unwrap().cursor_icon
+    }
+
+    /// Registers a custom cursor `surface` with its `hotspot` to show, whenever egui requests `icon`
+    ///
+    /// Passing `None` removes a previously registered cursor. The surface is not used by egui,
+    /// the mapping is only stored to be looked up via [`Self::cursor_override`].
+    pub fn set_cursor_override(
+        &self,
+        icon: CursorIcon,
+        surface_and_hotspot: Option<(WlSurface, Point<i32, Logical>)>,
+    ) {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .cursor_overrides
+            .retain(|(known, _, _)| *known != icon);
+        if let Some((surface, hotspot)) = surface_and_hotspot {
+            inner.cursor_overrides.push((icon, surface, hotspot));
+        }
+    }
+
+    /// Returns the custom cursor surface and hotspot registered for the current [`Self::cursor_icon`], if any
+    ///
+    /// Surfaces that were destroyed in the meantime are not returned.
+    pub fn cursor_override(&self) -> Option<(WlSurface, Point<i32, Logical>)> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .cursor_overrides
+            .iter()
+            .find(|(icon, surface, _)| *icon == inner.cursor_icon && surface.alive())
+            .map(|(_, surface, hotspot)| (surface.clone(), *hotspot))
+    }
+
+    /// Returns true, if egui reported editable text under the cursor during the last [`Self::render`] call
+    ///
+    /// IME bridges (e.g. for text-input-v3) can use this to decide how to style the surrounding text and preedit.
+    /// Like [`Self::cursor_icon`] this is cheap to call every frame.
+    pub fn mutable_text_under_cursor(&self) -> bool {
+        self.inner.lock().unwrap().mutable_text_under_cursor
+    }
+
+    /// Returns the rectangle of the widget, that has keyboard focus, if any
+    ///
+    /// Shells can use this to draw an additional, high-contrast focus indicator on top of egui.
+    /// The rect is in the same space as the `area` passed to [`Self::render`] and is taken from the last frame.
+    pub fn focus_rect(&self) -> Option<Rectangle<i32, Logical>> {
+        let focused = self.ctx.memory(|memory| memory.focused())?;
+        let rect = self.ctx.read_response(focused)?.rect;
+        let area = self.inner.lock().unwrap().area;
+        let focus_rect = Rectangle::<i32, Logical>::from_extemities(
+            (rect.min.x.floor() as i32, rect.min.y.floor() as i32),
+            (rect.max.x.ceil() as i32, rect.max.y.ceil() as i32),
+        );
+        Some(Rectangle::new(focus_rect.loc + area.loc, focus_rect.size))
+    }
+
+    /// Returns the repaints egui requested during the last [`Self::render`] call
+    ///
+    /// Every entry is a viewport together with the delay after which it wants to be repainted,
+    /// `Duration::ZERO` meaning as soon as possible. Viewports not asking for a repaint are omitted.
+    /// This allows scheduling repaints at the granularity egui asked for.
+    /// The delays are at least as long as [`EguiState::set_max_repaint_rate`] allows.
+    pub fn requested_repaints(&self) -> Vec<(ViewportId, Duration)> {
+        self.inner.lock().unwrap().requested_repaints.clone()
+    }
+
+    /// Returns when the next frame should be rendered
+    ///
+    /// - `Some(now)` while the user is interacting with egui (input is pending or the pointer is in use)
+    ///   or egui asked for an immediate repaint,
+    /// - the deadline of the earliest delayed repaint, while egui is animating,
+    /// - the time of the next key repeat, while a key is held, see [`EguiState::set_repeat_info`],
+    /// - `None` when egui is idle and only needs to be rendered again on new input.
+    ///
+    /// Repaints egui asks for by itself are throttled by [`EguiState::set_max_repaint_rate`], input is not.
+    pub fn next_frame_deadline(&self, now: Instant) -> Option<Instant> {
+        let inner = self.inner.lock().unwrap();
+        if !inner.events.is_empty() || self.ctx.is_using_pointer() {
+            return Some(now);
+        }
+
+        // generated input: the secondary click of a long press and key repeats
+        let synthetic = inner
+            .hold_start
+            .filter(|_| !inner.hold_pressed)
+            .map(|start| Ord::max(start + LONG_PRESS_DELAY, now))
+            .into_iter()
+            .chain(
+                inner
+                    .key_repeat
+                    .as_ref()
+                    .filter(|_| inner.repeat_info.0 > 0)
+                    .map(|repeat| Ord::max(repeat.next, now)),
+            )
+            .min();
+        let repaint = inner
+            .requested_repaints
+            .iter()
+            .map(|(_, delay)| now + *delay)
+            .chain(inner.repaint_deadline)
+            .min()
+            // egui was asked to repaint after the last frame was rendered
+            .or_else(|| self.ctx.has_requested_repaint().then_some(now))
+            .map(|repaint| match inner.last_paint {
+                Some(last_paint) => Ord::max(repaint, last_paint + inner.min_repaint_interval),
+                None => repaint,
+            });
+        match (synthetic, repaint) {
+            (Some(synthetic), Some(repaint)) => Some(Ord::min(synthetic, repaint)),
+            (synthetic, repaint) => synthetic.or(repaint),
+        }
+    }
+
+    /// Requests egui to be rendered again after `delay`, even if there is no input
+    ///
+    /// Use this, if something outside of egui changes the ui, e.g. a data update arrived.
+    /// The request is reflected by [`EguiState::next_frame_deadline`] until the next frame is rendered.
+    pub fn request_repaint_after(&self, delay: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        let deadline = Instant::now() + delay;
+        inner.repaint_deadline = Some(match inner.repaint_deadline {
+            Some(previous) => Ord::min(previous, deadline),
+            None => deadline,
+        });
+        self.ctx.request_repaint_after(delay);
+    }
+
+    /// Limits how often egui may ask to be repainted to `hz` times per second, `0` (the default) means unlimited
+    ///
+    /// Animations (like a spinner) keep running smoothly, just at a lower rate,
+    /// to save power. This affects [`EguiState::requested_repaints`] and [`EguiState::next_frame_deadline`].
+    pub fn set_max_repaint_rate(&self, hz: u32) {
+        self.inner.lock().unwrap().min_repaint_interval = match hz {
+            0 => Duration::ZERO,
+            hz => Duration::from_secs(1) / hz,
+        };
+    }
+
+    /// Forces the next render to recreate all buffers of this [`EguiState`], for every output
+    ///
+    /// Use this, if the buffers shouldn't be reused anymore, e.g. after switching the format
+    /// or color space of an output, so the change takes effect on the very next frame.
+    /// See [`EguiState::suspend`] to also recreate the rest of the GPU state.
+    pub fn invalidate_render_cache(&self) {
+        self.inner.lock().unwrap().invalidate_buffers = true;
+        self.ctx.request_repaint();
+    }
+
+    /// Marks the GPU state used by this [`EguiState`] as invalid, e.g. when the session is paused on a VT switch.
+    ///
+    /// Until [`EguiState::resume`] is called, rendering fails with [`EguiError::Suspended`]
+    /// without touching the renderer or running egui, so input queues up for the first frame after resuming.
+    /// A pass started with [`EguiState::begin_pass`] is still finished by [`EguiState::end_pass`],
+    /// its output (e.g. [`EguiState::copied_text`]) is kept and its texture updates are applied after resuming.
+    pub fn suspend(&self) {
+        self.inner.lock().unwrap().suspended = true;
+    }
+
+    /// Rebuilds the GPU state of `renderer` after [`EguiState::suspend`], e.g. when the session is resumed.
+    ///
+    /// The GPU state is shared by all `EguiState`s rendered with `renderer`, so the cached buffers of all of them
+    /// are dropped and each uploads its font atlas again on its next render.
+    /// Images loaded by egui's image loaders are reloaded automatically, other textures
+    /// (e.g. from [`Context::load_texture`] or `load_image`) need to be loaded again by the caller.
+    pub fn resume(&self, renderer: &mut impl BorrowMut<GlesRenderer>) -> Result<(), EguiError> {
+        let renderer = renderer.borrow_mut();
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.suspended {
+            return Ok(());
+        }
+
+        let existing = renderer
+            .egl_context()
+            .user_data()
+            .get::<UserDataType>()
+            .cloned();
+        if let Some(gl_state) = existing {
+            let mut state = new_gl_state(renderer)?;
+            std::mem::swap(&mut *RefCell::borrow_mut(&gl_state), &mut state);
+            // `state` now holds the previous painter
+            renderer.with_context(|_| {
+                let gl = state.painter.gl().clone();
+                // SAFETY: `with_context` makes the egl context of `renderer` current
+                unsafe { msaa::retain(&gl, &mut state.msaa_buffers, |_, _| false) };
+                state.painter.destroy();
+            })?;
+        }
+
+        inner.suspended = false;
+        self.ctx.request_repaint();
+        Ok(())
+    }
+
     /// Returns the egui [`PlatformOutput`] generated by the last [`Self::render`] call
+    ///
+    /// The output is cloned, so multiple consumers (e.g. for the cursor and the clipboard) can read it.
+    /// It stays around until the next render or [`Self::clear_output`], so call that after acting on it,
+    /// if repeated reads shouldn't trigger the same action (like opening a url) twice.
     pub fn last_output(&self) -> Option<PlatformOutput> {
-        self.inner.lock().unwrap().last_output.take()
+        self.inner.lock().unwrap().last_output.clone()
+    }
+
+    /// Returns the text egui wants to put into the clipboard, if the last [`Self::render`] call copied any
+    ///
+    /// Like [`Self::last_output`] this doesn't consume the output.
+    pub fn copied_text(&self) -> Option<String> {
+        self.inner
+            .lock()
+            .unwrap()
+            .last_output
+            .as_ref()
+            .map(|output| output.copied_text.clone())
+            .filter(|text| !text.is_empty())
+    }
+
+    /// Returns the url egui wants to open, if a link was clicked during the last [`Self::render`] call
+    ///
+    /// egui doesn't open urls by itself, the compositor is responsible for it, e.g. by spawning `xdg-open`.
+    /// [`egui::OpenUrl::new_tab`] is set, if the link was middle- or ctrl-clicked.
+    /// Like [`Self::last_output`] this doesn't consume the output, so [`Self::copied_text`] of the same frame
+    /// can still be read. Use [`Self::clear_output`] to not open the same url twice.
+    pub fn open_url(&self) -> Option<egui::OpenUrl> {
+        self.inner
+            .lock()
+            .unwrap()
+            .last_output
+            .as_ref()
+            .and_then(|output| output.open_url.clone())
+    }
+
+    /// Clears the [`PlatformOutput`] of the last [`Self::render`] call
+    ///
+    /// Afterwards [`Self::last_output`], [`Self::copied_text`] and [`Self::open_url`] return `None`
+    /// until the next frame is rendered.
+    pub fn clear_output(&self) {
+        self.inner.lock().unwrap().last_output = None;
+    }
+}
+
+/// Returns the [`GlState`] of `renderer`, creating it if necessary.
+fn gl_state(renderer: &mut GlesRenderer) -> Result<UserDataType, EguiError> {
+    if renderer
+        .egl_context()
+        .user_data()
+        .get::<UserDataType>()
+        .is_none()
+    {
+        let state = new_gl_state(renderer)?;
+        renderer
+            .egl_context()
+            .user_data()
+            .insert_if_missing(|| UserDataType::new(RefCell::new(state)));
+    }
+    Ok(renderer
+        .egl_context()
+        .user_data()
+        .get::<UserDataType>()
+        .unwrap()
+        .clone())
+}
+
+/// Creates a fresh [`GlState`] for `renderer`.
+fn new_gl_state(renderer: &mut GlesRenderer) -> Result<GlState, EguiError> {
+    let painter = renderer
+        .with_context(|_| {
+            // SAFETY: `with_context` makes the egl context of `renderer` current
+            let context = unsafe {
+                glow::Context::from_loader_function(|symbol| {
+                    smithay::backend::egl::get_proc_address(symbol) as *const _
+                })
+            };
+            // without a shader version, egui_glow probes the context: GLES3 gets `#version 300 es` shaders
+            // decoding textures as sRGB in hardware, only GLES2 falls back to the gamma math in the shader
+            Painter::new(Arc::new(context), "", None, false)
+        })?
+        .map_err(EguiError::Painter)?;
+    Ok(GlState {
+        generation: NEXT_GL_GENERATION.fetch_add(1, Ordering::Relaxed),
+        painter,
+        render_buffers: HashMap::new(),
+        effects_program: None,
+        effect_buffers: HashMap::new(),
+        msaa_buffers: HashMap::new(),
+        #[cfg(feature = "image")]
+        images: HashMap::new(),
+    })
+}
+
+/// Pushes the text event for the `utf8` string generated by a keystroke of `key`, if any.
+///
+/// Returns true, if text was pushed.
+fn push_text_event(
+    events: &mut Vec<Event>,
+    utf8: &str,
+    key: Option<egui::Key>,
+    text_input_active: bool,
+) -> bool {
+    // like egui-winit, only send a space as text while a text field is focused,
+    // so using it as a shortcut or to activate a button doesn't also insert text
+    if key == Some(egui::Key::Space) && !text_input_active {
+        return false;
+    }
+
+    /* utf8 can contain 1, multiple characters, or even be empty
+     *
+     * control characters (like "\r" for Enter or "\x08" for Backspace) are
+     * already covered by `Event::Key`, egui would otherwise insert them as text
+     */
+    let text = utf8.chars().filter(|c| !c.is_control()).collect::<String>();
+    if text.is_empty() {
+        return false;
+    }
+
+    // egui lays out combining marks poorly, so compose them with the text typed before (NFC),
+    // e.g. "e" followed by a combining acute accent becomes "é".
+    // the key events of the keystrokes (e.g. the release of "e") don't separate them.
+    let previous = events
+        .iter_mut()
+        .rev()
+        .find(|event| !matches!(event, Event::Key { .. }));
+    match previous {
+        Some(Event::Text(previous)) if text.chars().next().is_some_and(is_combining_mark) => {
+            *previous = previous.chars().chain(text.chars()).nfc().collect();
+        }
+        _ => events.push(Event::Text(text.nfc().collect())),
+    }
+    true
+}
+
+/// Returns the pending GL error as [`EguiError::Gl`], if the `gl_debug` feature is enabled.
+///
+/// The egl context needs to be current.
+#[cfg(feature = "gl_debug")]
+fn check_gl_error(gl: &glow::Context, step: &'static str) -> Result<(), EguiError> {
+    // SAFETY: the caller guarantees the context is current
+    match unsafe { gl.get_error() } {
+        glow::NO_ERROR => Ok(()),
+        error => Err(EguiError::Gl { step, error }),
+    }
+}
+
+#[cfg(not(feature = "gl_debug"))]
+fn check_gl_error(_gl: &glow::Context, _step: &'static str) -> Result<(), EguiError> {
+    Ok(())
+}
+
+#[cfg(feature = "gl_debug")]
+fn clear_gl_errors(gl: &glow::Context) {
+    // SAFETY: the caller guarantees the context is current
+    while unsafe { gl.get_error() } != glow::NO_ERROR {}
+}
+
+fn validate_scale(scale: Scale<f64>) -> Result<(), EguiError> {
+    match [scale.x, scale.y]
+        .into_iter()
+        .find(|factor| factor.is_nan() || *factor <= 0.0)
+    {
+        Some(invalid) => Err(EguiError::InvalidScale(invalid)),
+        None => Ok(()),
+    }
+}
+
+/// The integer scale of the underlying buffer per axis, big enough for the factors of `scale`
+fn buffer_scale(scale: Scale<f64>) -> Scale<i32> {
+    Scale::from((scale.x.ceil() as i32, scale.y.ceil() as i32))
+}
+
+/// Scales tessellated `primitives` from points to pixels, independently per axis
+fn stretch_primitives(primitives: &mut [ClippedPrimitive], scale: Vec2) {
+    let stretch_pos = |pos: Pos2| Pos2::new(pos.x * scale.x, pos.y * scale.y);
+    let stretch_rect =
+        |rect: Rect| Rect::from_min_max(stretch_pos(rect.min), stretch_pos(rect.max));
+    for ClippedPrimitive {
+        clip_rect,
+        primitive,
+    } in primitives
+    {
+        *clip_rect = stretch_rect(*clip_rect);
+        match primitive {
+            Primitive::Mesh(mesh) => {
+                for vertex in &mut mesh.vertices {
+                    vertex.pos = stretch_pos(vertex.pos);
+                }
+            }
+            Primitive::Callback(callback) => callback.rect = stretch_rect(callback.rect),
+        }
     }
 }
 
+/// egui has no notion of anisotropic scaling, so it uses the geometric mean of both factors
+fn pixels_per_point(scale: Scale<f64>) -> f32 {
+    (scale.x * scale.y).sqrt() as f32
+}
+
+/// Outputs are always painted at their current scale, regardless of the requested `scale`
+fn output_scale(output: Option<&Output>, scale: Scale<f64>) -> Scale<f64> {
+    output.map_or(scale, |output| {
+        Scale::from(output.current_scale().fractional_scale())
+    })
+}
+
+/// Scroll areas zero out the delta of any direction they actually scrolled in
+fn scroll_consumed(before: Vec2, after: Vec2) -> bool {
+    (before.x != 0.0 && after.x == 0.0) || (before.y != 0.0 && after.y == 0.0)
+}
+
+/// Returns the rects painted onto the background layer during the current pass, e.g. by panels
+#[cfg(feature = "desktop_integration")]
+fn background_region(ctx: &Context) -> Vec<Rect> {
+    ctx.graphics(|graphics| {
+        graphics
+            .get(egui::LayerId::background())
+            .into_iter()
+            .flat_map(|shapes| shapes.all_entries())
+            .map(|clipped| {
+                clipped
+                    .shape
+                    .visual_bounding_rect()
+                    .intersect(clipped.clip_rect)
+            })
+            .filter(|rect| rect.is_positive())
+            .collect()
+    })
+}
+
+/// Returns the area covered by `shapes`, including shadows
+fn used_rect(shapes: &[ClippedShape]) -> Option<Rectangle<i32, Logical>> {
+    let used = shapes
+        .iter()
+        .map(|clipped| {
+            clipped
+                .shape
+                .visual_bounding_rect()
+                .intersect(clipped.clip_rect)
+        })
+        .filter(|rect| rect.is_positive())
+        .reduce(|used, rect| used.union(rect))?;
+    Some(Rectangle::from_extemities(
+        (used.min.x.floor() as i32, used.min.y.floor() as i32),
+        (used.max.x.ceil() as i32, used.max.y.ceil() as i32),
+    ))
+}
+
+/// Restricts the clip rects of `primitives` to `damage`, duplicating primitives spanning multiple rects.
+///
+/// `damage` is expected to not contain overlapping rectangles.
+/// Appends the texture updates of a pass to those of passes, that weren't painted, in `pending`.
+///
+/// Updates, that are never visible, are dropped on the way: those of textures freed after an unpainted pass
+/// and those replaced by an upload of the whole texture. E.g. while suspended for many passes,
+/// the font atlas is uploaded at most once after resuming, instead of once for every pass that added glyphs.
+fn merge_textures(pending: &mut TexturesDelta, new: TexturesDelta) {
+    let TexturesDelta { set, free } = pending;
+    set.retain(|(id, _)| {
+        !free.contains(id)
+            && !new
+                .set
+                .iter()
+                .any(|(new_id, delta)| new_id == id && delta.pos.is_none())
+    });
+    pending.append(new);
+}
+
+/// Clips `primitives` to `damage`, a primitive is painted once for every damaged rect it touches.
+///
+/// Every painted mesh is uploaded into a new vertex buffer by egui_glow,
+/// so meshes are skipped for the damaged rects outside their bounds.
+fn clip_to_damage(
+    primitives: Vec<ClippedPrimitive>,
+    damage: &[Rectangle<i32, Logical>],
+) -> Vec<ClippedPrimitive> {
+    primitives
+        .into_iter()
+        .flat_map(
+            |ClippedPrimitive {
+                 clip_rect,
+                 primitive,
+             }| {
+                let bounds = match &primitive {
+                    Primitive::Mesh(mesh) => mesh.calc_bounds(),
+                    Primitive::Callback(callback) => callback.rect,
+                };
+                damage.iter().filter_map(move |rect| {
+                    let rect = Rect::from_min_size(
+                        Pos2::new(rect.loc.x as f32, rect.loc.y as f32),
+                        Vec2::new(rect.size.w as f32, rect.size.h as f32),
+                    );
+                    let clip_rect = clip_rect.intersect(rect);
+                    (clip_rect.is_positive() && clip_rect.intersects(bounds)).then(|| {
+                        ClippedPrimitive {
+                            clip_rect,
+                            primitive: primitive.clone(),
+                        }
+                    })
+                })
+            },
+        )
+        .collect()
+}
+
 impl IsAlive for EguiState {
     fn alive(&self) -> bool {
         true
@@ -559,7 +2980,7 @@ impl IsAlive for EguiState {
 
 impl<D: SeatHandler> PointerTarget<D> for EguiState {
     fn enter(&self, _seat: &Seat<D>, _data: &mut D, event: &MotionEvent) {
-        self.handle_pointer_motion(event.location.to_i32_floor())
+        self.pointer_enter(event.location)
     }
 
     fn motion(&self, _seat: &Seat<D>, _data: &mut D, event: &MotionEvent) {
@@ -569,24 +2990,28 @@ impl<D: SeatHandler> PointerTarget<D> for EguiState {
     fn relative_motion(&self, _seat: &Seat<D>, _data: &mut D, _event: &RelativeMotionEvent) {}
 
     fn button(&self, _seat: &Seat<D>, _data: &mut D, event: &ButtonEvent) {
-        if let Some(button) = match event.button {
-            0x110 => Some(MouseButton::Left),
-            0x111 => Some(MouseButton::Right),
-            0x112 => Some(MouseButton::Middle),
-            0x115 => Some(MouseButton::Forward),
-            0x116 => Some(MouseButton::Back),
-            _ => None,
-        } {
-            self.handle_pointer_button(button, event.state == ButtonState::Pressed)
+        if let Some(button) = convert_button_code(event.button) {
+            self.inner
+                .lock()
+                .unwrap()
+                .push_pointer_button(button, event.state == ButtonState::Pressed);
         }
     }
 
-    fn axis(&self, _seat: &Seat<D>, _data: &mut D, _frame: AxisFrame) {
-        // TODO
-        //self.handle_pointer_axis(frame., y_amount)
+    fn axis(&self, _seat: &Seat<D>, _data: &mut D, frame: AxisFrame) {
+        self.handle_axis_frame(&frame)
     }
 
-    fn leave(&self, _seat: &Seat<D>, _data: &mut D, _serial: Serial, _time: u32) {}
+    fn leave(&self, _seat: &Seat<D>, _data: &mut D, _serial: Serial, _time: u32) {
+        // the release of any held button goes elsewhere now, so egui would be stuck mid-drag
+        let mut inner = self.inner.lock().unwrap();
+        for button in std::mem::take(&mut inner.pressed_buttons) {
+            inner.push_pointer_button(button, false);
+        }
+        inner.hold_start = None;
+        inner.hold_pressed = false;
+        inner.push_pointer_gone();
+    }
 
     fn frame(&self, _seat: &Seat<D>, _data: &mut D) {}
 
@@ -616,9 +3041,47 @@ impl<D: SeatHandler> PointerTarget<D> for EguiState {
 
     fn gesture_pinch_end(&self, _seat: &Seat<D>, _data: &mut D, _event: &GesturePinchEndEvent) {}
 
-    fn gesture_hold_begin(&self, _seat: &Seat<D>, _data: &mut D, _event: &GestureHoldBeginEvent) {}
+    fn gesture_hold_begin(&self, _seat: &Seat<D>, _data: &mut D, _event: &GestureHoldBeginEvent) {
+        // a long press is turned into a secondary click by the first `render` after `LONG_PRESS_DELAY`,
+        // so context menus can be opened on touch devices
+        let mut inner = self.inner.lock().unwrap();
+        inner.hold_start = Some(Instant::now());
+        inner.hold_pressed = false;
+        self.ctx.request_repaint_after(LONG_PRESS_DELAY);
+    }
+
+    fn gesture_hold_end(&self, _seat: &Seat<D>, _data: &mut D, _event: &GestureHoldEndEvent) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.hold_start = None;
+        if std::mem::take(&mut inner.hold_pressed) {
+            inner.push_pointer_button(PointerButton::Secondary, false);
+        }
+    }
+}
+
+impl<D: SeatHandler> TouchTarget<D> for EguiState {
+    fn down(&self, _seat: &Seat<D>, _data: &mut D, event: &DownEvent, _seq: Serial) {
+        self.handle_touch_down(event.slot, event.location)
+    }
+
+    fn up(&self, _seat: &Seat<D>, _data: &mut D, event: &UpEvent, _seq: Serial) {
+        self.handle_touch_up(event.slot)
+    }
+
+    fn motion(&self, _seat: &Seat<D>, _data: &mut D, event: &TouchMotionEvent, _seq: Serial) {
+        self.handle_touch_motion(event.slot, event.location)
+    }
+
+    fn frame(&self, _seat: &Seat<D>, _data: &mut D, _seq: Serial) {}
+
+    fn cancel(&self, _seat: &Seat<D>, _data: &mut D, _seq: Serial) {
+        self.handle_touch_cancel()
+    }
+
+    fn shape(&self, _seat: &Seat<D>, _data: &mut D, _event: &ShapeEvent, _seq: Serial) {}
 
-    fn gesture_hold_end(&self, _seat: &Seat<D>, _data: &mut D, _event: &GestureHoldEndEvent) {}
+    fn orientation(&self, _seat: &Seat<D>, _data: &mut D, _event: &OrientationEvent, _seq: Serial) {
+    }
 }
 
 impl<D: SeatHandler> KeyboardTarget<D> for EguiState {
@@ -626,6 +3089,7 @@ impl<D: SeatHandler> KeyboardTarget<D> for EguiState {
         self.set_focused(true);
 
         let mut inner = self.inner.lock().unwrap();
+        let queued = inner.events.len();
         for handle in &keys {
             let key = if let Some(key) = convert_key(handle.raw_syms().iter().copied()) {
                 let modifiers = convert_modifiers(inner.last_modifiers);
@@ -645,6 +3109,8 @@ impl<D: SeatHandler> KeyboardTarget<D> for EguiState {
                 kbd.key_input(handle.raw_code().raw(), true);
             }
         }
+        // the modifiers of the new focus are only sent after `enter`
+        inner.unsynced_key_events = inner.events.len() - queued;
     }
 
     fn leave(&self, _seat: &Seat<D>, _data: &mut D, _serial: Serial) {
@@ -652,6 +3118,7 @@ impl<D: SeatHandler> KeyboardTarget<D> for EguiState {
 
         let keys = std::mem::take(&mut self.inner.lock().unwrap().pressed);
         let mut inner = self.inner.lock().unwrap();
+        inner.key_repeat = None;
         for (key, code) in keys {
             if let Some(key) = key {
                 let modifiers = convert_modifiers(inner.last_modifiers);
@@ -667,6 +3134,13 @@ impl<D: SeatHandler> KeyboardTarget<D> for EguiState {
                 kbd.key_input(code.raw(), false);
             }
         }
+        // modifiers aren't sent to unfocused clients, so neither keep the held ones,
+        // nor wait for an update after an `enter` that was never followed by one
+        inner.unsynced_key_events = 0;
+        if convert_modifiers(inner.last_modifiers) != egui::Modifiers::NONE {
+            self.ctx.request_repaint();
+        }
+        inner.last_modifiers = ModifiersState::default();
     }
 
     fn key(
@@ -679,7 +3153,7 @@ impl<D: SeatHandler> KeyboardTarget<D> for EguiState {
         _time: u32,
     ) {
         let modifiers = self.inner.lock().unwrap().last_modifiers;
-        self.handle_keyboard(&key, state == KeyState::Pressed, modifiers)
+        self.handle_keyboard(&key, state == KeyState::Pressed, modifiers);
     }
 
     fn modifiers(
@@ -689,7 +3163,7 @@ impl<D: SeatHandler> KeyboardTarget<D> for EguiState {
         modifiers: ModifiersState,
         _serial: Serial,
     ) {
-        self.inner.lock().unwrap().last_modifiers = modifiers;
+        self.update_modifiers(&mut self.inner.lock().unwrap(), modifiers);
     }
 }
 
@@ -700,20 +3174,38 @@ impl SpaceElement for EguiState {
     }
 
     fn is_in_input_region(&self, point: &Point<f64, Logical>) -> bool {
-        let pos: Point<i32, _> = point.to_i32_round();
-        let last_pos = self.inner.lock().unwrap().last_pointer_position;
-        if (pos.x - last_pos.x) + (pos.y - last_pos.y) < 10 {
-            self.wants_pointer()
-        } else {
-            false
+        let inner = self.inner.lock().unwrap();
+        let point = inner.scale_position(*point);
+        let pos = Pos2::new(point.x as f32, point.y as f32);
+        // windows and other areas are remembered by egui across passes,
+        // the background layer spans the whole area though, only what was painted onto it counts
+        match self.ctx.layer_id_at(pos) {
+            Some(layer) if layer.order != egui::Order::Background => true,
+            _ => inner
+                .background_region
+                .iter()
+                .any(|rect| rect.contains(pos)),
         }
     }
 
     fn set_activate(&self, _activated: bool) {}
-    fn output_enter(&self, _output: &smithay::output::Output, _overlap: Rectangle<i32, Logical>) {}
-    fn output_leave(&self, _output: &smithay::output::Output) {}
+    fn output_enter(&self, output: &Output, _overlap: Rectangle<i32, Logical>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .outputs
+            .retain(|known| known.is_alive() && known != output);
+        inner.outputs.push(output.downgrade());
+    }
+
+    fn output_leave(&self, output: &Output) {
+        self.inner
+            .lock()
+            .unwrap()
+            .outputs
+            .retain(|known| known.is_alive() && known != output);
+    }
 
     fn z_index(&self) -> u8 {
-        self.inner.lock().unwrap().z_index as u8
+        self.inner.lock().unwrap().z_index
     }
 }
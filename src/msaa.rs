@@ -0,0 +1,147 @@
+// Multisampled render targets egui is painted into, before being resolved into the egui surface.
+//
+// All functions need the egl context of the painter to be current.
+
+use egui_glow::glow::{self, HasContext};
+use smithay::utils::{Physical, Size};
+
+use std::{collections::HashMap, hash::Hash};
+
+#[derive(Debug)]
+pub struct MsaaBuffer {
+    framebuffer: glow::Framebuffer,
+    renderbuffer: glow::Renderbuffer,
+    size: Size<i32, Physical>,
+    // the sample count requested, the driver may support less
+    requested: u8,
+}
+
+impl MsaaBuffer {
+    /// Creates a new multisampled buffer, clamping `samples` to the maximum supported by the driver.
+    ///
+    /// Returns `None`, if multisampled rendering is not supported.
+    pub unsafe fn new(
+        gl: &glow::Context,
+        size: Size<i32, Physical>,
+        samples: u8,
+        label: &str,
+    ) -> Option<MsaaBuffer> {
+        // multisampled renderbuffers and blitting need GLES 3.0
+        if gl.version().major < 3 {
+            return None;
+        }
+        let supported = gl.get_parameter_i32(glow::MAX_SAMPLES).min(samples as i32);
+        if supported < 2 {
+            return None;
+        }
+
+        let previous = gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+        let renderbuffer = gl.create_renderbuffer().ok()?;
+        gl.bind_renderbuffer(glow::RENDERBUFFER, Some(renderbuffer));
+        gl.renderbuffer_storage_multisample(
+            glow::RENDERBUFFER,
+            supported,
+            glow::RGBA8,
+            size.w,
+            size.h,
+        );
+        gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+        let Ok(framebuffer) = gl.create_framebuffer() else {
+            gl.delete_renderbuffer(renderbuffer);
+            return None;
+        };
+        gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+        gl.framebuffer_renderbuffer(
+            glow::FRAMEBUFFER,
+            glow::COLOR_ATTACHMENT0,
+            glow::RENDERBUFFER,
+            Some(renderbuffer),
+        );
+        let complete = gl.check_framebuffer_status(glow::FRAMEBUFFER) == glow::FRAMEBUFFER_COMPLETE;
+        gl.bind_framebuffer(glow::FRAMEBUFFER, previous);
+
+        if gl.supports_debug() {
+            gl.object_label(
+                glow::FRAMEBUFFER,
+                framebuffer.0.get(),
+                Some(format!("{} msaa framebuffer", label)),
+            );
+            gl.object_label(
+                glow::RENDERBUFFER,
+                renderbuffer.0.get(),
+                Some(format!("{} msaa renderbuffer", label)),
+            );
+        }
+
+        let buffer = MsaaBuffer {
+            framebuffer,
+            renderbuffer,
+            size,
+            requested: samples,
+        };
+        if !complete {
+            buffer.destroy(gl);
+            return None;
+        }
+        Some(buffer)
+    }
+
+    /// Returns true, if this buffer was created for the given `size` and `samples`
+    pub fn matches(&self, size: Size<i32, Physical>, samples: u8) -> bool {
+        self.size == size && self.requested == samples
+    }
+
+    /// Binds and clears this buffer for painting, returns the previously bound framebuffer.
+    pub unsafe fn bind(&self, gl: &glow::Context) -> Option<glow::Framebuffer> {
+        let previous = gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+        gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer));
+        gl.disable(glow::SCISSOR_TEST);
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+        previous
+    }
+
+    /// Resolves the contents of this buffer into `target` and binds `target` again.
+    pub unsafe fn resolve(&self, gl: &glow::Context, target: Option<glow::Framebuffer>) {
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.framebuffer));
+        gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, target);
+        // blitting is affected by the scissor test
+        gl.disable(glow::SCISSOR_TEST);
+        gl.blit_framebuffer(
+            0,
+            0,
+            self.size.w,
+            self.size.h,
+            0,
+            0,
+            self.size.w,
+            self.size.h,
+            glow::COLOR_BUFFER_BIT,
+            glow::NEAREST,
+        );
+        gl.bind_framebuffer(glow::FRAMEBUFFER, target);
+    }
+
+    pub unsafe fn destroy(self, gl: &glow::Context) {
+        gl.delete_framebuffer(self.framebuffer);
+        gl.delete_renderbuffer(self.renderbuffer);
+    }
+}
+
+/// Destroys all buffers, `keep` returns false for
+pub unsafe fn retain<K: Clone + Eq + Hash>(
+    gl: &glow::Context,
+    buffers: &mut HashMap<K, MsaaBuffer>,
+    mut keep: impl FnMut(&K, &MsaaBuffer) -> bool,
+) {
+    let stale = buffers
+        .iter()
+        .filter(|(key, buffer)| !keep(key, buffer))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    for key in stale {
+        if let Some(buffer) = buffers.remove(&key) {
+            buffer.destroy(gl);
+        }
+    }
+}
@@ -0,0 +1,1163 @@
+// These tests run egui without a renderer, so they cover input handling and what egui makes of it.
+
+use super::*;
+use smithay::{
+    input::{
+        keyboard::{FilterResult, KeyboardHandle, XkbConfig},
+        SeatState,
+    },
+    utils::SERIAL_COUNTER,
+};
+
+struct State {
+    seat_state: SeatState<State>,
+}
+
+impl SeatHandler for State {
+    type KeyboardFocus = EguiState;
+    type PointerFocus = EguiState;
+    type TouchFocus = EguiState;
+
+    fn seat_state(&mut self) -> &mut SeatState<Self> {
+        &mut self.seat_state
+    }
+}
+
+// a keyboard with the given layout, focusing `egui`
+fn keyboard(egui: &EguiState, layout: &str) -> (State, KeyboardHandle<State>) {
+    let mut state = State {
+        seat_state: SeatState::new(),
+    };
+    let mut seat = state.seat_state.new_seat("seat-0");
+    let keyboard = seat
+        .add_keyboard(
+            XkbConfig {
+                layout,
+                ..XkbConfig::default()
+            },
+            200,
+            25,
+        )
+        .unwrap();
+    keyboard.set_focus(&mut state, Some(egui.clone()), SERIAL_COUNTER.next_serial());
+    (state, keyboard)
+}
+
+// sends a key with the given evdev `code` through the keyboard to its focus
+fn key(state: &mut State, keyboard: &KeyboardHandle<State>, code: u32, pressed: bool) {
+    keyboard.input::<(), _>(
+        state,
+        Keycode::new(code + 8),
+        match pressed {
+            true => KeyState::Pressed,
+            false => KeyState::Released,
+        },
+        SERIAL_COUNTER.next_serial(),
+        0,
+        |_, _, _| FilterResult::Forward,
+    );
+}
+
+fn tap(state: &mut State, keyboard: &KeyboardHandle<State>, code: u32) {
+    key(state, keyboard, code, true);
+    key(state, keyboard, code, false);
+}
+
+// runs a frame like `EguiState::render` does, without painting it
+fn run(egui: &EguiState, ui: impl FnMut(&Context)) -> FullOutput {
+    let mut inner = egui.inner.lock().unwrap();
+    let area = inner.area;
+    let mut full_output = egui.run(&mut inner, ui, area, 1.0, None, None);
+    egui.apply_output(
+        &mut inner,
+        std::mem::take(&mut full_output.platform_output),
+        std::mem::take(&mut full_output.viewport_output),
+    );
+    full_output
+}
+
+fn to_rectangle(rect: Rect) -> Rectangle<i32, Logical> {
+    Rectangle::from_extemities(
+        (rect.min.x.floor() as i32, rect.min.y.floor() as i32),
+        (rect.max.x.ceil() as i32, rect.max.y.ceil() as i32),
+    )
+}
+
+fn queued_text(egui: &EguiState) -> String {
+    egui.inner
+        .lock()
+        .unwrap()
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+const KEY_Q: u32 = 16;
+const KEY_RIGHTALT: u32 = 100;
+
+#[test]
+fn altgr_follows_the_compositor_keymap() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let (mut state, keyboard) = keyboard(&egui, "de");
+
+    key(&mut state, &keyboard, KEY_RIGHTALT, true);
+    tap(&mut state, &keyboard, KEY_Q);
+    key(&mut state, &keyboard, KEY_RIGHTALT, false);
+    tap(&mut state, &keyboard, KEY_Q);
+
+    assert_eq!(queued_text(&egui), "@q");
+
+    // switching back and forth has to be picked up, even if a keymap ends up at the address of a freed one
+    for (layout, text) in [("us", "q"), ("de", "@")] {
+        egui.inner.lock().unwrap().events.clear();
+        keyboard
+            .set_xkb_config(
+                &mut state,
+                XkbConfig {
+                    layout,
+                    ..XkbConfig::default()
+                },
+            )
+            .unwrap();
+        key(&mut state, &keyboard, KEY_RIGHTALT, true);
+        tap(&mut state, &keyboard, KEY_Q);
+        key(&mut state, &keyboard, KEY_RIGHTALT, false);
+        assert_eq!(queued_text(&egui), text);
+    }
+}
+
+#[test]
+fn used_rect_includes_windows_appearing_this_frame() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let full_output = run(&egui, |_| {});
+    assert_eq!(used_rect(&full_output.shapes), None);
+
+    // egui only measures a new window in the frame it is added and shows it in the next one
+    let mut window = None;
+    let mut show = |ctx: &Context| {
+        window = egui::Window::new("new")
+            .fixed_pos((400.0, 300.0))
+            .show(ctx, |ui| ui.label("appeared"))
+            .map(|response| response.response.rect);
+    };
+    run(&egui, &mut show);
+    let full_output = run(&egui, &mut show);
+    let window = to_rectangle(window.unwrap());
+    let used = used_rect(&full_output.shapes).unwrap();
+    assert_eq!(used.intersection(window), Some(window));
+}
+
+const KEY_ENTER: u32 = 28;
+const KEY_A: u32 = 30;
+const KEY_B: u32 = 48;
+
+// focuses a text edit, types `keys` and returns the text after the next frame
+fn type_into_text_edit(multiline: bool, keys: &[u32]) -> String {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let (mut state, keyboard) = keyboard(&egui, "us");
+    let mut text = String::new();
+    let mut ui = |ctx: &Context| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let edit = match multiline {
+                true => egui::TextEdit::multiline(&mut text),
+                false => egui::TextEdit::singleline(&mut text),
+            };
+            let response = ui.add(edit);
+            if !response.has_focus() {
+                response.request_focus();
+            }
+        });
+    };
+    // the field only reports text input to be active in the frame after it got focus
+    run(&egui, &mut ui);
+    run(&egui, &mut ui);
+    for code in keys {
+        tap(&mut state, &keyboard, *code);
+    }
+    run(&egui, &mut ui);
+    text
+}
+
+#[test]
+fn enter_inserts_newlines_only_in_multiline_fields() {
+    assert_eq!(
+        type_into_text_edit(true, &[KEY_A, KEY_ENTER, KEY_B]),
+        "a\nb"
+    );
+    // Enter submits a single line field, which gives up focus, so "b" goes nowhere
+    assert_eq!(type_into_text_edit(false, &[KEY_A, KEY_ENTER, KEY_B]), "a");
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct Mouse(&'static str);
+
+impl Device for Mouse {
+    fn id(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn name(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        capability == DeviceCapability::Pointer
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
+
+fn pointer_gone_count(egui: &EguiState) -> usize {
+    egui.inner
+        .lock()
+        .unwrap()
+        .events
+        .iter()
+        .filter(|event| matches!(event, Event::PointerGone))
+        .count()
+}
+
+#[test]
+fn pointer_is_gone_once_the_last_device_is_removed() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    egui.handle_device_added(&Mouse("mouse"));
+    egui.handle_device_added(&Mouse("touchpad"));
+    egui.handle_pointer_motion((10, 10).into());
+    egui.handle_device_removed(&Mouse("mouse"));
+    assert_eq!(pointer_gone_count(&egui), 0);
+    assert!(egui.pointer_in_area());
+    egui.handle_device_removed(&Mouse("touchpad"));
+    assert!(egui.inner.lock().unwrap().pointers.is_empty());
+    assert_eq!(pointer_gone_count(&egui), 1);
+    assert!(!egui.pointer_in_area());
+
+    // adding or removing a device twice doesn't throw off the count
+    egui.handle_device_added(&Mouse("mouse"));
+    egui.handle_device_added(&Mouse("mouse"));
+    egui.handle_device_removed(&Mouse("mouse"));
+    egui.handle_device_removed(&Mouse("mouse"));
+    assert!(egui.inner.lock().unwrap().pointers.is_empty());
+    assert_eq!(pointer_gone_count(&egui), 2);
+}
+
+#[test]
+fn resize_to_rejects_invalid_scales() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    for scale in [0.0, -1.0, f64::NAN] {
+        assert!(matches!(
+            egui.resize_to((1920, 1080).into(), scale),
+            Err(EguiError::InvalidScale(_))
+        ));
+    }
+    assert_eq!(egui.inner.lock().unwrap().area.size, (800, 600).into());
+    assert_eq!(
+        egui.resize_to((1920, 1080).into(), 2.0).unwrap().size,
+        (960, 540).into()
+    );
+}
+
+const KEY_SPACE: u32 = 57;
+
+#[test]
+fn space_activates_buttons_and_types_into_text_fields() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let (mut state, keyboard) = keyboard(&egui, "us");
+    let mut clicks = 0;
+    let mut ui = |ctx: &Context| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let response = ui.button("play");
+            if response.clicked() {
+                clicks += 1;
+            }
+            if !response.has_focus() {
+                response.request_focus();
+            }
+        });
+    };
+    run(&egui, &mut ui);
+    tap(&mut state, &keyboard, KEY_SPACE);
+    // no text field is focused, so the space is only a key press
+    assert_eq!(queued_text(&egui), "");
+    run(&egui, &mut ui);
+    assert_eq!(clicks, 1);
+
+    assert_eq!(
+        type_into_text_edit(false, &[KEY_A, KEY_SPACE, KEY_B]),
+        "a b"
+    );
+}
+
+const KEY_LEFTCTRL: u32 = 29;
+const KEY_S: u32 = 31;
+
+fn key_events(egui: &EguiState) -> Vec<(egui::Key, bool, egui::Modifiers)> {
+    egui.inner
+        .lock()
+        .unwrap()
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Key {
+                key,
+                pressed,
+                modifiers,
+                ..
+            } => Some((*key, *pressed, *modifiers)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn keys_held_across_focus_changes_carry_their_modifiers() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let (mut state, keyboard) = keyboard(&egui, "us");
+    let ctrl = convert_modifiers(ModifiersState {
+        ctrl: true,
+        ..ModifiersState::default()
+    });
+
+    // Ctrl+S is held, before egui gets focus
+    keyboard.set_focus(&mut state, None, SERIAL_COUNTER.next_serial());
+    key(&mut state, &keyboard, KEY_LEFTCTRL, true);
+    key(&mut state, &keyboard, KEY_S, true);
+    keyboard.set_focus(&mut state, Some(egui.clone()), SERIAL_COUNTER.next_serial());
+    assert_eq!(key_events(&egui), vec![(egui::Key::S, true, ctrl)]);
+
+    // losing focus releases the key and forgets the modifiers, that are no longer sent to egui
+    egui.inner.lock().unwrap().events.clear();
+    keyboard.set_focus(&mut state, None, SERIAL_COUNTER.next_serial());
+    assert_eq!(key_events(&egui), vec![(egui::Key::S, false, ctrl)]);
+    run(&egui, |_| {});
+    assert_eq!(
+        egui.context().input(|input| input.modifiers),
+        egui::Modifiers::NONE
+    );
+}
+
+fn output(scale: f64) -> Output {
+    let output = Output::new(
+        "test-output".into(),
+        smithay::output::PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: smithay::output::Subpixel::Unknown,
+            make: "smithay".into(),
+            model: "egui".into(),
+        },
+    );
+    output.change_current_state(
+        None,
+        None,
+        Some(smithay::output::Scale::Fractional(scale)),
+        None,
+    );
+    output
+}
+
+#[test]
+fn clones_for_outputs_use_the_scale_of_their_output() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let output = output(2.0);
+    let clone = egui.clone_for_output(&output);
+    let own_scale = |state: &EguiState| {
+        let own_output = state.output.as_ref().and_then(WeakOutput::upgrade);
+        output_scale(own_output.as_ref(), Scale::from(1.0))
+    };
+
+    assert_eq!(own_scale(&egui), Scale::from(1.0));
+    assert_eq!(own_scale(&clone), Scale::from(2.0));
+    output.change_current_state(
+        None,
+        None,
+        Some(smithay::output::Scale::Fractional(1.5)),
+        None,
+    );
+    assert_eq!(own_scale(&clone), Scale::from(1.5));
+}
+
+fn scroll_events(egui: &EguiState) -> Vec<(egui::MouseWheelUnit, Vec2)> {
+    egui.inner
+        .lock()
+        .unwrap()
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            Event::MouseWheel { unit, delta, .. } => Some((*unit, *delta)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn axis_frames_scroll_by_the_unit_of_their_source() {
+    use smithay::backend::input::AxisSource;
+    use smithay::input::pointer::AxisFrame;
+
+    for (source, unit, delta) in [
+        (
+            AxisSource::Wheel,
+            egui::MouseWheelUnit::Line,
+            Vec2::new(0.0, -0.5),
+        ),
+        (
+            AxisSource::Finger,
+            egui::MouseWheelUnit::Point,
+            Vec2::new(0.0, -10.0),
+        ),
+        (
+            AxisSource::Continuous,
+            egui::MouseWheelUnit::Point,
+            Vec2::new(0.0, -10.0),
+        ),
+    ] {
+        let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+        // a high resolution wheel, sending half a notch
+        let frame = AxisFrame::new(0)
+            .source(source)
+            .value(Axis::Vertical, 10.0)
+            .v120(Axis::Vertical, 60);
+        let mut state = State {
+            seat_state: SeatState::new(),
+        };
+        let seat = state.seat_state.new_seat("seat-0");
+        PointerTarget::axis(&egui, &seat, &mut state, frame);
+        assert_eq!(scroll_events(&egui), vec![(unit, delta)], "{source:?}");
+    }
+}
+
+const KEY_E: u32 = 18;
+
+#[test]
+fn combining_marks_compose_with_the_text_before() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let (mut state, keyboard) = keyboard(&egui, "us");
+    // Q types a combining acute accent
+    keyboard
+        .set_keymap_from_string(
+            &mut state,
+            r#"xkb_keymap {
+                xkb_keycodes { include "evdev+aliases(qwerty)" };
+                xkb_types { include "complete" };
+                xkb_compat { include "complete" };
+                xkb_symbols {
+                    include "pc+us+inet(evdev)"
+                    replace key <AD01> { [ U0301 ] };
+                };
+            };"#
+            .into(),
+        )
+        .unwrap();
+
+    tap(&mut state, &keyboard, KEY_E);
+    tap(&mut state, &keyboard, KEY_Q);
+    tap(&mut state, &keyboard, KEY_A);
+    assert_eq!(queued_text(&egui), "\u{e9}a");
+    // a mark without any text to compose with is kept as is
+    egui.inner.lock().unwrap().events.clear();
+    tap(&mut state, &keyboard, KEY_Q);
+    assert_eq!(queued_text(&egui), "\u{301}");
+}
+
+#[test]
+fn keysyms_map_to_egui_keys() {
+    use egui::Key;
+    use xkbcommon::xkb::Keysym;
+
+    // the function keys are consecutive keysyms
+    for n in 1..=35 {
+        assert_eq!(
+            convert_key(std::iter::once(Keysym::new(Keysym::F1.raw() + n - 1))),
+            Key::from_name(&format!("F{n}")),
+            "F{n}"
+        );
+    }
+
+    for (sym, key) in [
+        (Keysym::KP_0, Some(Key::Num0)),
+        (Keysym::KP_9, Some(Key::Num9)),
+        (Keysym::KP_Enter, Some(Key::Enter)),
+        (Keysym::KP_Add, Some(Key::Plus)),
+        (Keysym::KP_Subtract, Some(Key::Minus)),
+        (Keysym::KP_Divide, Some(Key::Slash)),
+        (Keysym::KP_Decimal, Some(Key::Period)),
+        (Keysym::KP_Separator, Some(Key::Comma)),
+        (Keysym::KP_Equal, Some(Key::Equals)),
+        (Keysym::KP_Up, Some(Key::ArrowUp)),
+        (Keysym::KP_Page_Down, Some(Key::PageDown)),
+        (Keysym::KP_Delete, Some(Key::Delete)),
+        (Keysym::colon, Some(Key::Colon)),
+        (Keysym::semicolon, Some(Key::Semicolon)),
+        (Keysym::apostrophe, Some(Key::Quote)),
+        (Keysym::grave, Some(Key::Backtick)),
+        (Keysym::backslash, Some(Key::Backslash)),
+        (Keysym::bar, Some(Key::Pipe)),
+        (Keysym::question, Some(Key::Questionmark)),
+        (Keysym::bracketleft, Some(Key::OpenBracket)),
+        (Keysym::bracketright, Some(Key::CloseBracket)),
+        (Keysym::comma, Some(Key::Comma)),
+        (Keysym::period, Some(Key::Period)),
+        (Keysym::Shift_L, None),
+        (Keysym::KP_Multiply, None),
+    ] {
+        assert_eq!(convert_key(std::iter::once(sym)), key, "{sym:?}");
+    }
+
+    // the first keysym, that maps to a key, wins
+    assert_eq!(
+        convert_key([Keysym::Shift_L, Keysym::KP_1, Keysym::_2].into_iter()),
+        Some(Key::Num1)
+    );
+}
+
+// a renderer on mesa's surfaceless platform, tests needing one are ignored by default
+// and run with `cargo test -- --ignored`
+fn renderer() -> Option<GlesRenderer> {
+    use smithay::backend::egl::{native::EGLSurfacelessDisplay, EGLContext, EGLDisplay};
+
+    let display = unsafe { EGLDisplay::new(EGLSurfacelessDisplay) }.ok()?;
+    let context = EGLContext::new(&display).ok()?;
+    unsafe { GlesRenderer::new(context) }.ok()
+}
+
+#[test]
+#[ignore = "needs EGL"]
+fn paint_callbacks_are_invoked_or_skipped() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let mut renderer = renderer().expect("no EGL display available");
+    let area = Rectangle::from_size((800, 600).into());
+    let egui = EguiState::new(area);
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let callback_calls = calls.clone();
+    let glow_callback = Arc::new(egui_glow::CallbackFn::new(move |_, _| {
+        callback_calls.fetch_add(1, Ordering::SeqCst);
+    }));
+    let rect = Rect::from_min_size(Pos2::new(10.0, 10.0), Vec2::new(100.0, 100.0));
+    egui.render(
+        |ctx| {
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            painter.add(egui::PaintCallback {
+                rect,
+                callback: glow_callback.clone(),
+            });
+            // only the glow renderer's callbacks can be painted, anything else is skipped
+            painter.add(egui::PaintCallback {
+                rect,
+                callback: Arc::new(()),
+            });
+        },
+        &mut renderer,
+        area,
+        1.0,
+        1.0,
+    )
+    .unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn pastes_are_queued_and_inserted_into_text_fields() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let mut text = String::new();
+    let mut ui = |ctx: &Context| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.text_edit_singleline(&mut text).request_focus();
+        });
+    };
+    run(&egui, &mut ui);
+
+    egui.handle_paste("pasted text".into());
+    assert!(matches!(
+        egui.inner.lock().unwrap().events.as_slice(),
+        [Event::Paste(pasted)] if pasted == "pasted text"
+    ));
+    run(&egui, &mut ui);
+    assert_eq!(text, "pasted text");
+}
+
+#[test]
+fn clicked_hyperlinks_are_opened_by_the_compositor() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let link = std::cell::Cell::new(Rect::NOTHING);
+    let mut ui = |ctx: &Context| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            link.set(ui.hyperlink("https://smithay.github.io").rect);
+        });
+    };
+    run(&egui, &mut ui);
+    assert!(egui.open_url().is_none());
+
+    let link = to_rectangle(link.get());
+    egui.handle_pointer_motion(link.loc + link.size.downscale(2));
+    run(&egui, &mut ui);
+    egui.handle_pointer_button(MouseButton::Left, true);
+    run(&egui, &mut ui);
+    egui.handle_pointer_button(MouseButton::Left, false);
+    // copying in the same frame doesn't hide the url
+    run(&egui, |ctx: &Context| {
+        ui(ctx);
+        ctx.copy_text("copied".into());
+    });
+
+    let url = egui.open_url().unwrap();
+    assert_eq!(url.url, "https://smithay.github.io");
+    assert!(!url.new_tab);
+    assert_eq!(egui.copied_text().as_deref(), Some("copied"));
+}
+
+#[test]
+#[ignore = "needs EGL"]
+fn every_output_gets_a_buffer_at_its_scale() {
+    let mut renderer = renderer().expect("no EGL display available");
+    let area = Rectangle::from_size((400, 300).into());
+    let egui = EguiState::new(area);
+    let (output_1, output_2) = (output(1.0), output(2.0));
+    let buffer_scales = |renderer: &mut GlesRenderer| {
+        let mut scales = gl_state(renderer)
+            .unwrap()
+            .borrow()
+            .render_buffers
+            .iter()
+            .filter(|((id, _), _)| *id == egui.id())
+            .map(|(_, buffer)| buffer.scale.x)
+            .collect::<Vec<_>>();
+        scales.sort();
+        scales
+    };
+
+    for output in [&output_1, &output_2] {
+        egui.render_for_output(output, |_| {}, &mut renderer, area, 1.0)
+            .unwrap();
+    }
+    assert_eq!(buffer_scales(&mut renderer), vec![1, 2]);
+
+    // rendering one output again doesn't touch the buffer of the other
+    egui.render_for_output(&output_1, |_| {}, &mut renderer, area, 1.0)
+        .unwrap();
+    assert_eq!(buffer_scales(&mut renderer), vec![1, 2]);
+
+    // buffers of destroyed outputs are dropped on the next render
+    drop(output_2);
+    egui.render_for_output(&output_1, |_| {}, &mut renderer, area, 1.0)
+        .unwrap();
+    assert_eq!(buffer_scales(&mut renderer), vec![1]);
+}
+
+#[cfg(feature = "persistence")]
+#[test]
+fn memory_survives_a_save_and_load() {
+    let id = egui::Id::new("persisted");
+    let window_rect = |egui: &EguiState, default_pos: (f32, f32)| {
+        let mut rect = Rect::NOTHING;
+        // new windows are only shown in their second frame
+        for _ in 0..2 {
+            run(egui, |ctx| {
+                rect = egui::Window::new("window")
+                    .default_pos(default_pos)
+                    .show(ctx, |ui| ui.label("content"))
+                    .unwrap()
+                    .response
+                    .rect;
+            });
+        }
+        rect
+    };
+
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let rect = window_rect(&egui, (100.0, 100.0));
+    egui.with_memory(|memory| memory.data.insert_persisted(id, 42u32));
+    let data = egui.save_memory();
+
+    let restored = EguiState::new(Rectangle::from_size((800, 600).into()));
+    restored.load_memory(&data);
+    assert_eq!(
+        restored.with_memory(|memory| memory.data.get_persisted::<u32>(id)),
+        Some(42)
+    );
+    // the window stays where it was, instead of opening at its new default position
+    assert_eq!(window_rect(&restored, (300.0, 300.0)), rect);
+
+    // garbage keeps the current memory
+    restored.load_memory(b"not ron");
+    assert_eq!(
+        restored.with_memory(|memory| memory.data.get_persisted::<u32>(id)),
+        Some(42)
+    );
+}
+
+#[cfg(feature = "desktop_integration")]
+#[test]
+fn input_region_covers_panels_and_areas() {
+    use smithay::desktop::space::SpaceElement;
+
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let is_in_input_region =
+        |x: f32, y: f32| SpaceElement::is_in_input_region(&egui, &(x as f64, y as f64).into());
+    assert!(!is_in_input_region(400.0, 300.0));
+
+    let (mut button, mut panel) = (Rect::NOTHING, Rect::NOTHING);
+    // new areas are only shown in their second frame
+    for _ in 0..2 {
+        run(&egui, |ctx| {
+            panel = egui::SidePanel::left("panel")
+                .exact_width(200.0)
+                .show(ctx, |ui| ui.label("panel"))
+                .response
+                .rect;
+            button = egui::Area::new(egui::Id::new("area"))
+                .fixed_pos((400.0, 300.0))
+                .show(ctx, |ui| ui.button("button"))
+                .inner
+                .rect;
+        });
+    }
+
+    // the pointer never moved, so this can't rely on what egui knows about the pointer
+    assert!(is_in_input_region(button.center().x, button.center().y));
+    assert!(is_in_input_region(panel.center().x, panel.bottom() - 1.0));
+    assert!(!is_in_input_region(
+        button.right() + 50.0,
+        button.center().y
+    ));
+    assert!(!is_in_input_region(panel.right() + 50.0, panel.center().y));
+}
+
+#[test]
+fn held_keys_repeat_at_most_once_per_dispatch() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    let (mut state, keyboard) = keyboard(&egui, "us");
+    // repeats start after 200ms and then happen 25 times a second
+    egui.set_repeat_info(25, 200);
+    let repeats = |now: Instant| {
+        egui.inner.lock().unwrap().events.clear();
+        egui.dispatch_repeat(now);
+        egui.inner
+            .lock()
+            .unwrap()
+            .events
+            .iter()
+            .filter(|event| matches!(event, Event::Key { repeat: true, .. }))
+            .count()
+    };
+
+    let start = Instant::now();
+    key(&mut state, &keyboard, KEY_A, true);
+    let pressed = Instant::now();
+    assert_eq!(repeats(start + Duration::from_millis(100)), 0);
+    assert_eq!(repeats(pressed + Duration::from_millis(200)), 1);
+    assert_eq!(queued_text(&egui), "a");
+    assert_eq!(repeats(pressed + Duration::from_millis(210)), 0);
+    assert_eq!(repeats(pressed + Duration::from_millis(240)), 1);
+
+    // a stall of many intervals still only repeats once, and the next repeat follows an interval later
+    let stalled = pressed + Duration::from_secs(2);
+    assert_eq!(repeats(stalled), 1);
+    assert_eq!(repeats(stalled + Duration::from_millis(20)), 0);
+    assert_eq!(repeats(stalled + Duration::from_millis(40)), 1);
+
+    key(&mut state, &keyboard, KEY_A, false);
+    assert_eq!(repeats(stalled + Duration::from_secs(1)), 0);
+}
+
+// draws onto a transparent buffer of `size` like a compositor would and returns its pixels
+fn composite(
+    renderer: &mut GlesRenderer,
+    size: Size<i32, Physical>,
+    draw: impl FnOnce(&mut GlesFrame<'_>),
+) -> Vec<u8> {
+    use smithay::backend::renderer::ExportMem;
+
+    let buffer_size = size.to_logical(1).to_buffer(1, Transform::Normal);
+    let target: GlesTexture = renderer
+        .create_buffer(Fourcc::Abgr8888, buffer_size)
+        .unwrap();
+    renderer.bind(target).unwrap();
+    let mut frame = renderer.render(size, Transform::Normal).unwrap();
+    let damage = [Rectangle::from_size(size)];
+    frame.clear([0.0, 0.0, 0.0, 0.0].into(), &damage).unwrap();
+    draw(&mut frame);
+    let _ = frame.finish().unwrap();
+    let mapping = renderer
+        .copy_framebuffer(Rectangle::from_size(buffer_size), Fourcc::Abgr8888)
+        .unwrap();
+    renderer.map_texture(&mapping).unwrap().to_vec()
+}
+
+// renders a red `rect` with `render` and checks, that it ends up in the right place
+fn assert_painted_at(
+    renderer: &mut GlesRenderer,
+    render: impl FnOnce(&mut GlesRenderer, &dyn Fn(&Context)) -> TextureRenderElement<GlesTexture>,
+    rect: Rect,
+    scale: Scale<i32>,
+) {
+    let element = render(renderer, &|ctx: &Context| {
+        ctx.layer_painter(egui::LayerId::background())
+            .rect_filled(rect, 0.0, Color32::RED);
+    });
+    let geometry = element.geometry(scale.to_f64());
+    let painted = composite(renderer, geometry.size, |frame| {
+        RenderElement::<GlesRenderer>::draw(
+            &element,
+            frame,
+            element.src(),
+            geometry,
+            &[Rectangle::from_size(geometry.size)],
+            &[],
+        )
+        .unwrap()
+    });
+    let expected = composite(renderer, geometry.size, |frame| {
+        let rect = to_rectangle(rect).to_physical(scale);
+        frame
+            .draw_solid(
+                rect,
+                &[Rectangle::from_size(rect.size)],
+                [1.0, 0.0, 0.0, 1.0].into(),
+            )
+            .unwrap()
+    });
+    // egui feathers the edges, so only compare which pixels are mostly covered
+    let covered = |pixels: Vec<u8>| {
+        pixels
+            .chunks(4)
+            .map(|pixel| pixel[3] > 127)
+            .collect::<Vec<_>>()
+    };
+    assert!(
+        covered(painted) == covered(expected),
+        "{rect:?} was painted elsewhere"
+    );
+}
+
+#[test]
+#[ignore = "needs EGL"]
+fn moving_content_is_repainted_in_place() {
+    let mut renderer = renderer().expect("no EGL display available");
+    let area = Rectangle::from_size((400, 300).into());
+
+    for (scale, corner_radius) in [(1, 0.0), (2, 0.0), (1, 4.0)] {
+        let egui = EguiState::new(area);
+        // rounded corners are applied in a second pass
+        egui.set_corner_radius(corner_radius);
+        let mut rect = Rect::from_min_size(Pos2::new(100.0, 100.0), Vec2::new(100.0, 50.0));
+        // only the parts of the buffer, that changed, are repainted after the first frame
+        for _ in 0..3 {
+            assert_painted_at(
+                &mut renderer,
+                |renderer, ui| egui.render(ui, renderer, area, scale as f64, 1.0).unwrap(),
+                rect,
+                Scale::from(scale),
+            );
+            rect = rect.translate(Vec2::new(50.0, 25.0));
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs EGL"]
+fn anisotropic_scales_are_painted_at_the_resolution_of_each_axis() {
+    let mut renderer = renderer().expect("no EGL display available");
+    let area = Rectangle::from_size((400, 300).into());
+    let scale = Scale::from((1, 2));
+    let egui = EguiState::new(area);
+
+    let mut rect = Rect::from_min_size(Pos2::ZERO, Vec2::new(100.0, 50.0));
+    for _ in 0..3 {
+        assert_painted_at(
+            &mut renderer,
+            |renderer, ui| {
+                let element = egui
+                    .render_scaled(ui, renderer, area, scale.to_f64(), 1.0)
+                    .unwrap();
+                // the buffer has the resolution of each axis and isn't scaled any further
+                assert_eq!(element.src(), Rectangle::from_size((400.0, 600.0).into()));
+                assert_eq!(
+                    element.geometry(scale.to_f64()),
+                    Rectangle::from_size((400, 600).into())
+                );
+                element
+            },
+            rect,
+            scale,
+        );
+        rect = rect.translate(Vec2::new(50.0, 25.0));
+    }
+}
+
+#[test]
+fn touch_points_are_scaled_and_clamped_like_the_pointer() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    egui.set_pointer_scale(2.0);
+    egui.set_pointer_region(Some(Rectangle::from_size((100, 100).into())));
+    let slot = TouchSlot::from(Some(0));
+    let positions = || {
+        let mut inner = egui.inner.lock().unwrap();
+        std::mem::take(&mut inner.events)
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Touch { pos, .. } | Event::PointerMoved(pos) => Some(pos),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    egui.handle_touch_down(slot, (10.0, 20.0).into());
+    assert_eq!(positions(), [Pos2::new(20.0, 40.0); 2]);
+    egui.handle_touch_motion(slot, (80.0, 30.0).into());
+    assert_eq!(positions(), [Pos2::new(100.0, 60.0); 2]);
+    egui.handle_touch_up(slot);
+    assert_eq!(positions(), [Pos2::new(100.0, 60.0)]);
+}
+
+#[test]
+fn interactive_widgets_are_found_with_the_pointer_scale() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    egui.set_pointer_scale(2.0);
+    let button = std::cell::Cell::new(Rect::NOTHING);
+    let mut ui = |ctx: &Context| {
+        egui::Area::new(egui::Id::new("button"))
+            .fixed_pos((300.0, 300.0))
+            .show(ctx, |ui| button.set(ui.button("button").rect));
+    };
+    // areas only settle on their position in the second frame
+    run(&egui, &mut ui);
+    run(&egui, &mut ui);
+
+    // positions are passed in the space of the compositor, which is half the one of egui
+    let center = button.get().center();
+    let center = Point::<f64, Logical>::from((center.x as f64, center.y as f64)).downscale(2.0);
+    egui.handle_pointer_motion(center.to_i32_round());
+    run(&egui, &mut ui);
+    assert!(egui.interactive_at(center));
+    assert!(!egui.interactive_at(center.upscale(2.0)));
+}
+
+// only checked by debug assertions
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic = "still open"]
+fn passes_cannot_be_nested() {
+    let egui = EguiState::new(Rectangle::from_size((800, 600).into()));
+    egui.begin_pass();
+    egui.begin_pass();
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[ignore = "needs EGL"]
+#[should_panic = "still open"]
+fn rendering_during_a_pass_is_caught() {
+    let mut renderer = renderer().expect("no EGL display available");
+    let area = Rectangle::from_size((400, 300).into());
+    let egui = EguiState::new(area);
+    egui.begin_pass();
+    let _ = egui.render(|_| {}, &mut renderer, area, 1.0, 1.0);
+}
+
+#[test]
+#[ignore = "needs EGL"]
+fn resuming_uploads_the_textures_of_every_state_again() {
+    let mut renderer = renderer().expect("no EGL display available");
+    let area = Rectangle::from_size((400, 300).into());
+    let states = [EguiState::new(area), EguiState::new(area)];
+    let render = |egui: &EguiState, renderer: &mut GlesRenderer| {
+        egui.render(
+            |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| ui.label("text"));
+            },
+            renderer,
+            area,
+            1.0,
+            1.0,
+        )
+        .unwrap();
+        egui.uploaded_textures_last_frame()
+    };
+    for egui in &states {
+        assert!(render(egui, &mut renderer));
+        assert!(!render(egui, &mut renderer));
+    }
+
+    // both states share the gpu state of the renderer, resuming one replaces it for both
+    states[0].suspend();
+    states[0].resume(&mut renderer).unwrap();
+    for egui in &states {
+        assert!(render(egui, &mut renderer));
+        assert!(!render(egui, &mut renderer));
+    }
+}
+
+#[test]
+#[ignore = "needs EGL"]
+fn semi_transparent_images_are_not_darkened() {
+    let mut renderer = renderer().expect("no EGL display available");
+    let area = Rectangle::from_size((64, 64).into());
+    let egui = EguiState::new(area);
+    // egui keeps color images premultiplied, even when created from straight alpha
+    let color = Color32::from_rgba_unmultiplied(255, 255, 255, 128);
+    let image = egui::ColorImage::new([16, 16], color);
+    let texture = egui
+        .ctx
+        .load_texture("white", image, egui::TextureOptions::NEAREST);
+    let element = egui
+        .render(
+            |ctx| {
+                ctx.layer_painter(egui::LayerId::background()).image(
+                    texture.id(),
+                    Rect::from_min_size(Pos2::ZERO, Vec2::splat(64.0)),
+                    Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                    Color32::WHITE,
+                );
+            },
+            &mut renderer,
+            area,
+            1.0,
+            1.0,
+        )
+        .unwrap();
+    let size = element.geometry(1.0.into()).size;
+    let painted = composite(&mut renderer, size, |frame| {
+        RenderElement::<GlesRenderer>::draw(
+            &element,
+            frame,
+            element.src(),
+            Rectangle::from_size(size),
+            &[Rectangle::from_size(size)],
+            &[],
+        )
+        .unwrap()
+    });
+
+    // the premultiplied color ends up in the buffer as is, like the compositor expects it.
+    // premultiplying it again on the way would darken it, e.g. at the edges of images.
+    let center = &painted[(32 * 64 + 32) * 4..][..4];
+    for (channel, expected) in center.iter().zip(color.to_array()) {
+        assert!(
+            channel.abs_diff(expected) <= 2,
+            "{center:?} instead of {color:?}"
+        );
+    }
+}
+
+#[test]
+fn texture_updates_of_unpainted_passes_are_merged() {
+    let full = |color| ImageDelta::full(egui::ColorImage::new([64, 64], color), Default::default());
+    let glyph = |x| {
+        ImageDelta::partial(
+            [x, 0],
+            egui::ColorImage::new([8, 8], Color32::WHITE),
+            Default::default(),
+        )
+    };
+    let (atlas, image) = (TextureId::default(), TextureId::Managed(1));
+    let pass =
+        |set: Vec<(TextureId, ImageDelta)>, free: Vec<TextureId>| TexturesDelta { set, free };
+
+    let mut pending = TexturesDelta::default();
+    merge_textures(
+        &mut pending,
+        pass(vec![(atlas, full(Color32::BLACK))], vec![]),
+    );
+    for x in 0..10 {
+        merge_textures(&mut pending, pass(vec![(atlas, glyph(x * 8))], vec![]));
+    }
+    merge_textures(
+        &mut pending,
+        pass(vec![(image, full(Color32::RED))], vec![]),
+    );
+    merge_textures(&mut pending, pass(vec![], vec![image]));
+    assert_eq!(pending.set.len(), 12);
+
+    // the atlas grew and was uploaded as a whole, the image was freed
+    merge_textures(
+        &mut pending,
+        pass(vec![(atlas, full(Color32::WHITE))], vec![]),
+    );
+    let uploads = pending
+        .set
+        .iter()
+        .map(|(id, delta)| (*id, delta.pos))
+        .collect::<Vec<_>>();
+    assert_eq!(uploads, [(atlas, None)]);
+    assert_eq!(pending.free, [image]);
+}
+
+#[test]
+#[ignore = "needs EGL"]
+fn only_repainted_parts_are_reported_as_damage() {
+    use smithay::backend::renderer::element::Element;
+
+    let mut renderer = renderer().expect("no EGL display available");
+    let area = Rectangle::from_size((400, 300).into());
+    let egui = EguiState::new(area);
+    let mut render = |rect: Rect| {
+        egui.render(
+            |ctx| {
+                ctx.layer_painter(egui::LayerId::background())
+                    .rect_filled(rect, 0.0, Color32::RED);
+            },
+            &mut renderer,
+            area,
+            1.0,
+            1.0,
+        )
+        .unwrap()
+    };
+    let rect = Rect::from_min_size(Pos2::new(100.0, 100.0), Vec2::new(50.0, 50.0));
+    let first = render(rect);
+    let moved = rect.translate(Vec2::new(20.0, 0.0));
+    let second = render(moved);
+
+    assert_eq!(first.id(), second.id());
+    let damage = second.damage_since(1.0.into(), Some(first.current_commit()));
+    let damaged = damage
+        .iter()
+        .copied()
+        .reduce(|damaged, rect| damaged.merge(rect))
+        .unwrap();
+    // egui feathers the edges, so the damage may grow by a pixel
+    let expected = to_rectangle(rect.union(moved)).to_physical(1);
+    let feathered = Rectangle::from_extemities(
+        expected.loc - Point::from((2, 2)),
+        expected.loc + expected.size + Size::from((2, 2)),
+    );
+    assert!(
+        damaged.contains_rect(expected) && feathered.contains_rect(damaged),
+        "{damaged:?} isn't the area the rect moved in"
+    );
+}
+
+#[test]
+fn meshes_are_only_painted_for_the_damage_they_touch() {
+    let mesh = |rect: Rect| {
+        let mut mesh = egui::Mesh::default();
+        mesh.add_colored_rect(rect, Color32::RED);
+        ClippedPrimitive {
+            clip_rect: Rect::EVERYTHING,
+            primitive: Primitive::Mesh(mesh),
+        }
+    };
+    let left = Rect::from_min_size(Pos2::new(10.0, 10.0), Vec2::splat(20.0));
+    let across = Rect::from_min_size(Pos2::new(10.0, 10.0), Vec2::new(200.0, 20.0));
+    let damage = [
+        Rectangle::new((0, 0).into(), (50, 50).into()),
+        Rectangle::new((150, 0).into(), (50, 50).into()),
+    ];
+
+    let clip_rects = clip_to_damage(vec![mesh(left), mesh(across)], &damage)
+        .into_iter()
+        .map(|primitive| to_rectangle(primitive.clip_rect))
+        .collect::<Vec<_>>();
+    assert_eq!(clip_rects, [damage[0], damage[0], damage[1]]);
+}